@@ -0,0 +1,44 @@
+//! Timing of `str_table_hash` over string lengths and byte distributions.
+//!
+//! Run with `cargo bench --bench str_table_hash`.
+
+use std::{hint::black_box, time::Instant};
+
+use desynced_exchange::value::str_table_hash;
+
+const LENGTHS: [usize; 5] = [1, 8, 32, 128, 512];
+const ITERATIONS: u32 = 100_000;
+
+fn ascii_string(len: usize) -> String {
+    (0 .. len).map(|i| char::from(b'!' + (i % 94) as u8)).collect()
+}
+
+fn full_byte_string(len: usize) -> String {
+    // two-byte characters cover the 0x80..=0xBF range of the bytes,
+    // padded with a single ASCII byte for odd lengths
+    let mut string: String = (0 .. len / 2)
+        .map(|i| char::from_u32(0x80 + (i % 0x780) as u32).unwrap())
+        .collect();
+    if len % 2 == 1 { string.push('~'); }
+    string
+}
+
+fn bench(name: &str, value: &str) {
+    let start = Instant::now();
+    for _ in 0 .. ITERATIONS {
+        black_box(str_table_hash(black_box(value)));
+    }
+    let elapsed = start.elapsed();
+    println!( "{name:>10} len {len:>3}: {ns:>8.2} ns/iter",
+        len = value.len(),
+        ns = elapsed.as_secs_f64() * 1e9 / f64::from(ITERATIONS) );
+}
+
+fn main() {
+    for len in LENGTHS {
+        bench("ascii", &ascii_string(len));
+    }
+    for len in LENGTHS {
+        bench("full-byte", &full_byte_string(len));
+    }
+}
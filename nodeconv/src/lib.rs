@@ -0,0 +1,34 @@
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use serde_json as json;
+
+use desynced_exchange::{
+    error::LoadError,
+    dumper::dump_blueprint as dump,
+    loader::load_blueprint as load,
+    value::Value,
+    blueprint::Exchange,
+};
+
+/// Decode into a plain JavaScript object
+/// (an externally tagged `Blueprint` or `Behavior` value tree).
+#[napi]
+pub fn load_blueprint(encoded: String) -> Result<json::Value> {
+    let value = load::<_,_,LoadError>(&encoded)
+        .map_err(|error| Error::from_reason(error.to_string()))?
+        .transpose().ok_or_else(|| Error::from_reason(
+            "Blueprint or behavior should not \
+            be represented with nil" ))?;
+    json::to_value::<Exchange<Value>>(value)
+        .map_err(|error| Error::from_reason(error.to_string()))
+}
+
+/// Encode an object of the same shape as `load_blueprint` returns.
+#[napi]
+pub fn dump_blueprint(decoded: json::Value) -> Result<String> {
+    let value = json::from_value::<Exchange<Value>>(decoded)
+        .map_err(|error| Error::from_reason(error.to_string()))?;
+    dump(value.map_mono(Some))
+        .map_err(|error| Error::from_reason(error.to_string()))
+}
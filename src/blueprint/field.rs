@@ -0,0 +1,138 @@
+//! Marker types for accessing fields of an `Exchange` by indexing,
+//! e.g. `exchange[field::Name]`.
+//! Indexing panics if the exchange is of the wrong kind.
+
+#![allow( clippy::exhaustive_structs,
+    reason = "markers are unit structs meant to be constructed by users" )]
+
+use std::collections::btree_map::BTreeMap as SortedMap;
+
+use crate::{Str, Exchange};
+
+use super::{Blueprint, Behavior, Component, Parameter, Instruction, Value};
+
+pub trait BlueprintField<P> {
+    type Output: ?Sized;
+    fn get(self, blueprint: &P) -> &Self::Output;
+}
+
+pub trait BehaviorField<B> {
+    type Output: ?Sized;
+    fn get(self, behavior: &B) -> &Self::Output;
+}
+
+macro_rules! define_field {
+    (Blueprint $field:ident: $output:ty = $name:ident) => {
+        #[derive(Debug, Clone, Copy)]
+        pub struct $field;
+        impl BlueprintField<Blueprint> for $field {
+            type Output = $output;
+            fn get(self, blueprint: &Blueprint) -> &$output {
+                &blueprint.$name
+            }
+        }
+        impl<P, B> std::ops::Index<$field> for Exchange<P, B>
+        where $field: BlueprintField<P>
+        {
+            type Output = <$field as BlueprintField<P>>::Output;
+            fn index(&self, field: $field) -> &Self::Output {
+                match self {
+                    Self::Blueprint(blueprint) => field.get(blueprint),
+                    Self::Behavior(_) => panic!("exchange is not a blueprint"),
+                }
+            }
+        }
+    };
+    (Behavior $field:ident: $output:ty = $name:ident) => {
+        #[derive(Debug, Clone, Copy)]
+        pub struct $field;
+        impl BehaviorField<Behavior> for $field {
+            type Output = $output;
+            fn get(self, behavior: &Behavior) -> &$output {
+                &behavior.$name
+            }
+        }
+        impl<P, B> std::ops::Index<$field> for Exchange<P, B>
+        where $field: BehaviorField<B>
+        {
+            type Output = <$field as BehaviorField<B>>::Output;
+            fn index(&self, field: $field) -> &Self::Output {
+                match self {
+                    Self::Behavior(behavior) => field.get(behavior),
+                    Self::Blueprint(_) => panic!("exchange is not a behavior"),
+                }
+            }
+        }
+    };
+}
+
+define_field!(Blueprint Frame: Str = frame);
+define_field!(Blueprint Components: Vec<Component> = components);
+define_field!(Blueprint Registers: Vec<Option<Value>> = registers);
+define_field!(Blueprint Links: Vec<(i32, i32)> = links);
+define_field!(Blueprint Locks: Vec<Option<Str>> = locks);
+define_field!(Blueprint Logistics: SortedMap<Str, bool> = logistics);
+
+define_field!(Behavior Description: Option<Str> = description);
+define_field!(Behavior Parameters: Vec<Parameter> = parameters);
+define_field!(Behavior Instructions: Vec<Instruction> = instructions);
+define_field!(Behavior Subroutines: Vec<Behavior> = subroutines);
+
+/// The name, which both blueprints and behaviors have.
+#[derive(Debug, Clone, Copy)]
+pub struct Name;
+
+impl BlueprintField<Blueprint> for Name {
+    type Output = Option<Str>;
+    fn get(self, blueprint: &Blueprint) -> &Option<Str> {
+        &blueprint.name
+    }
+}
+
+impl BehaviorField<Behavior> for Name {
+    type Output = Option<Str>;
+    fn get(self, behavior: &Behavior) -> &Option<Str> {
+        &behavior.name
+    }
+}
+
+impl<P, B, O: ?Sized> std::ops::Index<Name> for Exchange<P, B>
+where
+    Name: BlueprintField<P, Output=O> + BehaviorField<B, Output=O>,
+{
+    type Output = O;
+    fn index(&self, field: Name) -> &O {
+        match self {
+            Self::Blueprint(blueprint) =>
+                BlueprintField::get(field, blueprint),
+            Self::Behavior(behavior) =>
+                BehaviorField::get(field, behavior),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+use crate::Exchange;
+
+use super::super::{Blueprint, Behavior};
+use super::{Name, Frame, Instructions};
+
+#[test]
+fn test_field_index() {
+    let exchange = Exchange::<Blueprint, Behavior>::Behavior(Behavior {
+        name: Some("behavior".into()),
+        ..Behavior::default()
+    });
+    assert_eq!(exchange[Name].as_deref(), Some("behavior"));
+    assert!(exchange[Instructions].is_empty());
+    let blueprint = Exchange::<Blueprint, Behavior>::Blueprint(Blueprint {
+        frame: "f_building1x1a".into(),
+        ..Blueprint::default()
+    });
+    assert_eq!(&*blueprint[Frame], "f_building1x1a");
+    assert_eq!(blueprint[Name], None);
+}
+
+}
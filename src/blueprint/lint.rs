@@ -0,0 +1,117 @@
+use crate::value::{Key, Value, Table};
+
+/// Tables nested deeper than this are reported.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum LintSeverity {
+    /// Probably harmless, but may be suboptimal.
+    Info,
+    /// Probably a mistake.
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LintWarning {
+    pub severity: LintSeverity,
+    pub message: String,
+}
+
+impl LintWarning {
+    fn new(severity: LintSeverity, path: &[Key], message: &str) -> Self {
+        Self { severity, message: format!("{path:?}: {message}") }
+    }
+}
+
+/// Check a loaded blueprint (or behavior) value for
+/// common authoring mistakes.
+#[must_use]
+pub fn lint_blueprint(value: &Value) -> Vec<LintWarning> {
+    let mut linter = Linter { path: Vec::new(), warnings: Vec::new() };
+    linter.lint_value(value);
+    linter.warnings
+}
+
+struct Linter {
+    path: Vec<Key>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter {
+
+    fn warn(&mut self, severity: LintSeverity, message: &str) {
+        self.warnings.push(LintWarning::new(severity, &self.path, message));
+    }
+
+    fn lint_value(&mut self, value: &Value) {
+        let Value::Table(table) = value else { return };
+        if self.path.len() >= MAX_DEPTH {
+            self.warn(LintSeverity::Warning,
+                "tables are nested too deeply" );
+            return;
+        }
+        self.lint_table(table);
+    }
+
+    fn lint_table(&mut self, table: &Table) {
+        let sparse_count = table.iter_hash()
+            .filter(|&(key, _)| matches!(*key, Key::Index(1 ..)))
+            .count();
+        if sparse_count > 0 {
+            self.warn(LintSeverity::Info, &format!(
+                "{sparse_count} positive integer key(s) \
+                 do not fit into the array part" ));
+        }
+        for (key, value) in table {
+            if let Key::Name(ref name) = key {
+                if name.is_empty() {
+                    self.warn(LintSeverity::Warning,
+                        "table has an empty string key" );
+                }
+                if let Ok(index) = name.parse::<i32>() {
+                    if table.get(&Key::Index(index)).is_some() {
+                        self.warn(LintSeverity::Warning, &format!(
+                            "table has both {index:?} and {name:?} keys" ));
+                    }
+                }
+            }
+            self.path.push(key);
+            self.lint_value(value);
+            self.path.pop();
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+use crate::value::{Key, Value, Table};
+
+use super::{lint_blueprint, LintSeverity, MAX_DEPTH};
+
+#[test]
+fn test_lint() {
+    let table = |items: Vec<(Key, Value)>|
+        Value::Table(items.into_iter().collect::<Table>());
+    let value = table(vec![
+        (Key::from(""), Value::Integer(1)),
+        (Key::Index(1), Value::Integer(1)),
+        (Key::from("1"), Value::Integer(1)),
+        (Key::Index(100), Value::Integer(1)),
+    ]);
+    let warnings = lint_blueprint(&value);
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(warnings.iter()
+        .filter(|w| w.severity == LintSeverity::Info).count(), 1);
+    let mut nested = Value::Integer(0);
+    for _ in 0 ..= MAX_DEPTH {
+        nested = table(vec![(Key::from("x"), nested)]);
+    }
+    assert_eq!(lint_blueprint(&nested).len(), 1);
+    assert!(lint_blueprint(&table(vec![])).is_empty());
+}
+
+}
@@ -25,6 +25,11 @@ pub use instruction::Instruction;
 mod operand;
 pub use operand::{Operand, Jump, Place, Value};
 
+pub mod field;
+
+mod lint;
+pub use lint::{lint_blueprint, LintWarning, LintSeverity};
+
 fn bool_true() -> bool { true }
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
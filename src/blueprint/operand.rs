@@ -120,6 +120,8 @@ impl TryFrom<_Value> for Operand {
                 Value::try_from(table)? )),
             _Value::Float(_) => return Err(LoadError::from(
                 "operand cannot be a float" )),
+            _Value::Bytes(_) => return Err(LoadError::from(
+                "operand cannot be a non-UTF-8 string" )),
             _Value::Boolean(true) => return Err(LoadError::from(
                 "operand cannot be `true`" )),
             _Value::Integer(i32::MIN ..= 0) =>
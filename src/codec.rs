@@ -0,0 +1,56 @@
+//! Unification of `load` and `dump` traits
+//! for types that can go both ways.
+
+use crate::{
+    error::{LoadError, DumpError},
+    load::{KeyLoad, Load},
+    dump::{KeyDump, Dump},
+};
+
+pub trait Codec : Load + Dump {}
+
+impl<T: Load + Dump> Codec for T {}
+
+pub trait KeyCodec : KeyLoad + KeyDump {}
+
+impl<T: KeyLoad + KeyDump> KeyCodec for T {}
+
+/// Binary encoding of a value, as found in exchange strings
+/// after decompression.
+pub trait WireFormat : Sized {
+    fn encode(&self) -> Result<Vec<u8>, DumpError>;
+    fn decode(bytes: &[u8]) -> Result<Self, LoadError>;
+}
+
+impl<T: Codec> WireFormat for T {
+    fn encode(&self) -> Result<Vec<u8>, DumpError> {
+        crate::dumper::encode_ref(Some(self))
+    }
+    fn decode(bytes: &[u8]) -> Result<Self, LoadError> {
+        crate::loader::decode(bytes)?
+            .ok_or_else(|| LoadError::from("value should not be nil"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+use crate::value::{Key, Value, Table};
+
+use super::WireFormat;
+
+#[test]
+fn test_wire_format_roundtrip() {
+    let value = Value::Table(Table::from_iter([
+        (Key::Index(1), Value::Integer(-5)),
+        (Key::from("name"), Value::String("x".into())),
+    ]));
+    let bytes = value.encode().unwrap();
+    assert!(Value::decode(&bytes).unwrap() == value);
+    assert_eq!(Value::Integer(5).encode().unwrap(), [0x05]);
+    let Err(error) = Value::decode(&[0xC0])
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("nil"), "{error}");
+}
+
+}
@@ -3,6 +3,8 @@ pub trait Read<B: Copy> {
     fn read_slice(&mut self, len: usize) -> Option<&[B]>;
     fn read_end_slice(&mut self, len: usize) -> Option<&[B]>;
     fn read_rest(&mut self) -> &[B];
+    /// Up to `len` next elements, without consuming them.
+    fn peek_slice(&self, len: usize) -> &[B];
 
     #[inline]
     fn read_array<const N: usize>(&mut self) -> Option<[B; N]> {
@@ -45,6 +47,10 @@ impl<B: Copy> Read<B> for &[B] {
         Some(slice)
     }
     #[inline]
+    fn peek_slice(&self, len: usize) -> &[B] {
+        &self[.. usize::min(len, self.len())]
+    }
+    #[inline]
     fn read_end_slice(&mut self, len: usize) -> Option<&[B]> {
         if self.len() < len { return None; }
         let (rest, slice) = self.split_at(self.len() - len);
@@ -75,6 +81,9 @@ where R: Read<B>
     fn read_rest(&mut self) -> &[B]
     { R::read_rest(self) }
     #[inline]
+    fn peek_slice(&self, len: usize) -> &[B]
+    { R::peek_slice(self, len) }
+    #[inline]
     fn read_array<const N: usize>(&mut self) -> Option<[B; N]>
     { R::read_array(self) }
     #[inline]
@@ -121,3 +130,104 @@ where W: Write<B>
     { W::write_byte(self, value) }
 }
 
+
+/// Adapter reading bytes from an `io::Read` as they are asked for.
+///
+/// Only the requested slices are kept in memory. The length of the data
+/// cannot be known in advance, so it is declared on creation.
+/// An I/O error ends the data early, and is kept until `finish`.
+pub(crate) struct IoRead<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    /// Slice removed from the end by `read_end_slice`
+    tail: Vec<u8>,
+    declared_len: usize,
+    consumed_len: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<R: std::io::Read> IoRead<R> {
+    const CHUNK_LEN: usize = 4096;
+
+    pub(crate) fn new(reader: R, declared_len: usize) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(), position: 0,
+            tail: Vec::new(),
+            declared_len, consumed_len: 0,
+            error: None,
+        }
+    }
+
+    fn available_len(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    /// Try to make at least `len` bytes available in the buffer.
+    fn fill(&mut self, len: usize) -> bool {
+        use std::io::Read as _;
+        if self.available_len() >= len { return true; }
+        if self.error.is_some() { return false; }
+        self.buffer.drain(..self.position);
+        self.position = 0;
+        let missing = usize::max(len - self.buffer.len(), Self::CHUNK_LEN);
+        let missing = u64::try_from(missing).unwrap_or(u64::MAX);
+        if let Err(error) = (&mut self.reader).take(missing)
+            .read_to_end(&mut self.buffer)
+        {
+            self.error = Some(error);
+        }
+        self.buffer.len() >= len
+    }
+
+    fn fill_all(&mut self) {
+        if self.error.is_some() { return; }
+        if let Err(error) = self.reader.read_to_end(&mut self.buffer) {
+            self.error = Some(error);
+        }
+    }
+
+    /// Read the rest of the data, returning the inner reader
+    /// and the total length of the data.
+    pub(crate) fn finish(mut self) -> Result<(R, usize), std::io::Error> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let rest_len = std::io::copy(&mut self.reader, &mut std::io::sink())?;
+        let len = self.consumed_len
+            .saturating_add(self.available_len())
+            .saturating_add(usize::try_from(rest_len).unwrap_or(usize::MAX));
+        Ok((self.reader, len))
+    }
+}
+
+impl<R: std::io::Read> Read<u8> for IoRead<R> {
+    fn len(&self) -> usize {
+        self.declared_len.saturating_sub(self.consumed_len)
+    }
+    fn read_slice(&mut self, len: usize) -> Option<&[u8]> {
+        if !self.fill(len) { return None; }
+        let start = self.position;
+        self.position += len;
+        self.consumed_len += len;
+        Some(&self.buffer[start .. start + len])
+    }
+    fn peek_slice(&self, len: usize) -> &[u8] {
+        &self.buffer[self.position..][.. usize::min(len, self.available_len())]
+    }
+    fn read_end_slice(&mut self, len: usize) -> Option<&[u8]> {
+        self.fill_all();
+        if self.available_len() < len { return None; }
+        self.tail = self.buffer.split_off(self.buffer.len() - len);
+        self.consumed_len += len;
+        Some(&self.tail)
+    }
+    fn read_rest(&mut self) -> &[u8] {
+        self.fill_all();
+        let start = self.position;
+        self.position = self.buffer.len();
+        self.consumed_len += self.position - start;
+        &self.buffer[start..]
+    }
+}
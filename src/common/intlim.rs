@@ -350,10 +350,20 @@ where W: Write<u8>, CS: CheckSum<u32>
         self.writer.write_slice(&word.to_le_bytes()[..decoded_len]);
         Ok(())
     }
-    pub(crate) fn end(mut self) -> Result<(W, CS), LoadError> {
+    /// Decoded data written so far.
+    pub(crate) fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    /// Decode the shorter last word, if any.
+    /// Only valid when there is no more data.
+    pub(crate) fn end_word(&mut self) -> Result<(), LoadError> {
         if self.buffer_len > 0 {
             self.decode_word()?;
         }
+        Ok(())
+    }
+    pub(crate) fn end(mut self) -> Result<(W, CS), LoadError> {
+        self.end_word()?;
         let Self { writer, buffer_len, checksum, .. } = self;
         assert!(buffer_len == 0);
         Ok((writer, checksum))
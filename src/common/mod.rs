@@ -11,6 +11,8 @@ pub(crate) mod serde;
 #[must_use]
 #[inline]
 pub(crate) const fn u32_to_usize(len: u32) -> usize {
+    //! Does not compile on targets where `usize` is narrower than `u32`.
+    //! See `try_u32_to_usize` for a fallible conversion.
     assert!({ const OK: bool = {
         let ok = u32::BITS <= usize::BITS;
         assert!(ok); ok
@@ -18,19 +20,51 @@ pub(crate) const fn u32_to_usize(len: u32) -> usize {
     len as usize
 }
 
+#[inline]
+pub(crate) fn try_u32_to_usize(len: u32)
+-> Result<usize, std::num::TryFromIntError> {
+    usize::try_from(len)
+}
+
 pub type LogSize = u8;
 
 #[must_use]
 #[inline]
 pub(crate) const fn iexp2(loglen: Option<LogSize>) -> u32 {
-    let Some(loglen) = loglen else { return 0 };
-    match 1_u32.checked_shl(loglen as u32) {
-        Some(exp) if exp - 1 <= (i32::MAX as u32) => exp,
-        _ => panic!( "differences between indices \
+    match iexp2_checked(loglen) {
+        Some(exp) => exp,
+        None => panic!( "differences between indices \
             should be expressable by i32" ),
     }
 }
 
+#[must_use]
+#[inline]
+pub(crate) const fn iexp2_checked(loglen: Option<LogSize>) -> Option<u32> {
+    //! Same as `iexp2`, but returns `None` instead of panicking
+    //! when differences between indices would not fit into `i32`.
+    let Some(loglen) = loglen else { return Some(0) };
+    match 1_u32.checked_shl(loglen as u32) {
+        Some(exp) if exp - 1 <= (i32::MAX as u32) => Some(exp),
+        _ => None,
+    }
+}
+
+#[must_use]
+pub(crate) fn lossless_float_to_integer(value: f64) -> Option<i32> {
+    //! Returns `None` unless `value` is exactly representable as `i32`.
+    //! Negative zero is converted to zero.
+    if !(f64::from(i32::MIN) ..= f64::from(i32::MAX)).contains(&value) {
+        return None;
+    }
+    if value.fract() != 0.0 {
+        return None;
+    }
+    #[allow( clippy::cast_possible_truncation,
+        reason = "the value is integral and within the range" )]
+    Some(value as i32)
+}
+
 #[derive(Debug, Error)]
 #[error("The number should be a power of two")]
 pub(crate) struct NotPowerOfTwoError;
@@ -182,3 +216,37 @@ impl<const N: usize, T> ConstSlice<N, T> {
     }
 }
 
+
+#[cfg(test)]
+mod test {
+
+use super::{ilog2_ceil, lossless_float_to_integer};
+
+#[test]
+fn test_ilog2_ceil() {
+    assert_eq!(ilog2_ceil(0), None);
+    assert_eq!(ilog2_ceil(1), Some(0));
+    assert_eq!(ilog2_ceil(2), Some(1));
+    assert_eq!(ilog2_ceil(3), Some(2));
+    assert_eq!(ilog2_ceil(4), Some(2));
+    assert_eq!(ilog2_ceil(5), Some(3));
+    assert_eq!(ilog2_ceil(1 << 20), Some(20));
+    assert_eq!(ilog2_ceil((1 << 20) + 1), Some(21));
+    let bits = u8::try_from(usize::BITS).unwrap();
+    assert_eq!(ilog2_ceil(usize::MAX), Some(bits));
+}
+
+#[test]
+fn test_lossless_float_to_integer() {
+    assert_eq!(lossless_float_to_integer(0.0), Some(0));
+    assert_eq!(lossless_float_to_integer(-0.0), Some(0));
+    assert_eq!(lossless_float_to_integer(-42.0), Some(-42));
+    assert_eq!(lossless_float_to_integer(f64::from(i32::MAX)), Some(i32::MAX));
+    assert_eq!(lossless_float_to_integer(f64::from(i32::MIN)), Some(i32::MIN));
+    assert_eq!(lossless_float_to_integer(f64::from(i32::MAX) + 1.0), None);
+    assert_eq!(lossless_float_to_integer(0.5), None);
+    assert_eq!(lossless_float_to_integer(f64::NAN), None);
+    assert_eq!(lossless_float_to_integer(f64::INFINITY), None);
+}
+
+}
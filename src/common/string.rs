@@ -66,6 +66,54 @@ impl Str {
     pub fn shared(string: SharedStr) -> Self {
         Self::Shared(string)
     }
+    /// Strings in blueprints are UTF-8 (not necessarily ASCII),
+    /// so this only checks that `bytes` are valid UTF-8.
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, std::str::Utf8Error> {
+        Ok(Self::new(std::str::from_utf8(bytes)?))
+    }
+    #[must_use]
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self
+    }
+    #[must_use]
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+    /// Length in bytes, same as `str::len`.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.as_str().len()
+    }
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+    /// A copy of the string cut to at most `max_len` bytes.
+    /// Lossy: the cut is moved back to the nearest character boundary,
+    /// and everything after it is dropped.
+    #[must_use]
+    pub fn truncate(&self, max_len: usize) -> Self {
+        if self.len() <= max_len {
+            return self.clone();
+        }
+        let mut len = max_len;
+        loop {
+            if let Some(truncated) = self.get(..len) {
+                return Self::new(truncated);
+            }
+            len -= 1;
+        }
+    }
+    #[must_use]
+    #[inline]
+    pub fn contains_only_ascii(&self) -> bool {
+        self.as_bytes().is_ascii()
+    }
 }
 
 impl Default for Str {
@@ -172,6 +220,37 @@ use crate::common::{
 
 use super::Str;
 
+#[test]
+fn str_from_bytes() {
+    assert_eq!(Str::from_bytes(b"asdf").unwrap().as_bytes(), b"asdf");
+    assert_eq!(&*Str::from_bytes("фыва".as_bytes()).unwrap(), "фыва");
+    let Err(error) = Str::from_bytes(b"as\xFF")
+        else { panic!("should be an error") };
+    assert_eq!(error.valid_up_to(), 2);
+}
+
+#[test]
+fn str_len() {
+    assert_eq!(Str::known("фыва").len(), 8);
+    assert!(Str::default().is_empty());
+    assert!(!Str::from("a").is_empty());
+}
+
+#[test]
+fn str_truncate() {
+    assert_eq!(&*Str::known("asdf").truncate(2), "as");
+    assert_eq!(&*Str::known("asdf").truncate(10), "asdf");
+    assert_eq!(&*Str::known("фыва").truncate(3), "ф");
+    assert_eq!(&*Str::known("фыва").truncate(0), "");
+}
+
+#[test]
+fn str_contains_only_ascii() {
+    assert!(Str::known("asdf").contains_only_ascii());
+    assert!(Str::default().contains_only_ascii());
+    assert!(!Str::known("фыва").contains_only_ascii());
+}
+
 #[test]
 fn str_option_flat_serde_ron() {
     for (s, s1) in [
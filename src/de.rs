@@ -2,6 +2,7 @@ use serde::de::{self, Error as _};
 
 use crate::{
     error::LoadError as Error,
+    common::lossless_float_to_integer,
     value::{Key, Value, Table},
     Exchange,
 };
@@ -283,6 +284,7 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer {
             Some(Value::Float  (value)) => visitor.visit_f64(value),
             Some(Value::String (value)) =>
                 visitor.visit_str(value.as_ref()),
+            Some(Value::Bytes  (value)) => visitor.visit_byte_buf(value),
             Some(Value::Table  (table)) =>
                 visitor.visit_map(TableMapDeserializer::new(table)),
         }
@@ -290,13 +292,30 @@ impl<'de> de::Deserializer<'de> for ValueDeserializer {
 
     serde::forward_to_deserialize_any!(
         bool
-        i8 i16 i32 i64
+        i8 i16 i64
         u8 u16 u32 u64
         f32 f64
         char str string bytes byte_buf
         identifier ignored_any
     );
 
+    fn deserialize_i32<V>(self, visitor: V)
+    -> Result<V::Value, Self::Error>
+    where V: de::Visitor<'de>
+    {
+        //! The game does not always distinguish integers and floats,
+        //! so integral floats are accepted.
+        match self.0 {
+            Some(Value::Float(value)) => match
+                lossless_float_to_integer(value)
+            {
+                Some(value) => visitor.visit_i32(value),
+                None => visitor.visit_f64(value),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
     fn deserialize_option<V>(self, visitor: V)
     -> Result<V::Value, Self::Error>
     where V: de::Visitor<'de>
@@ -42,7 +42,26 @@ pub trait Dumper : Sized {
     fn dump_boolean(self, value: bool) -> Result<Self::Ok, Self::Error>;
     fn dump_integer(self, value: i32) -> Result<Self::Ok, Self::Error>;
     fn dump_float(self, value: f64) -> Result<Self::Ok, Self::Error>;
+    /// Dump an integer that may be outside of the `i32` range.
+    /// By default, such values are dumped as the nearest float,
+    /// since there is no way to construct `Self::Error` here;
+    /// the exchange format dumper rejects values that would be rounded.
+    fn dump_i64(self, value: i64) -> Result<Self::Ok, Self::Error> {
+        #![allow(clippy::cast_precision_loss, reason = "documented rounding")]
+        match i32::try_from(value) {
+            Ok(value) => self.dump_integer(value),
+            Err(_) => self.dump_float(value as f64),
+        }
+    }
     fn dump_string(self, value: &str) -> Result<Self::Ok, Self::Error>;
+    /// Dump a string that is not necessarily valid UTF-8.
+    /// By default, valid UTF-8 is dumped with `dump_string`
+    /// and invalid sequences are replaced with U+FFFD,
+    /// since there is no way to construct `Self::Error` here;
+    /// the exchange format dumper writes the bytes as they are.
+    fn dump_bytes(self, value: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.dump_string(&String::from_utf8_lossy(value))
+    }
     fn dump_table<'v, T>(self, table: T) -> Result<Self::Ok, Self::Error>
     where
         T: TableDumpIter<'v>,
@@ -9,25 +9,41 @@ use crate::{
         KeyDump, Dump, TableDumpIter,
         Dumper as DumperTr, KeyDumper,
     },
+    value::constants::{self, MAX_MIXED_ARRAY_LEN, MAX_STRING_LEN},
     Exchange,
 };
 
 mod compress;
 
-const EXCEEDED_LOGLEN: LogSize = crate::MAX_ASSOC_LOGLEN + 1;
+const EXCEEDED_LOGLEN: LogSize = constants::MAX_ASSOC_LOGLEN + 1;
+const EXCEEDED_MIXED_ARRAY_LEN: u32 = constants::MAX_MIXED_ARRAY_LEN + 1;
 
 pub fn dump_blueprint<P, H>(exchange: Exchange<Option<P>, Option<H>>)
 -> Result<String, Error>
 where P: Dump, H: Dump
 {
-    let encoded_body = exchange.map(encode, encode).transpose()?;
+    let encoded_body = dump_blueprint_to_bytes(exchange)?;
     Ok(compress::compress(encoded_body.as_deref()))
 }
 
+/// Same as `dump_blueprint`, but stop at the binary encoding,
+/// before compression and the text encoding.
+/// The kind of the exchange is kept as the variant.
+pub fn dump_blueprint_to_bytes<P, H>(exchange: Exchange<Option<P>, Option<H>>)
+-> Result<Exchange<Vec<u8>>, Error>
+where P: Dump, H: Dump
+{
+    exchange.map(encode, encode).transpose()
+}
+
 #[inline]
 fn encode<V: Dump>(value: Option<V>) -> Result<Vec<u8>, Error> {
+    encode_ref(value.as_ref())
+}
+
+pub(crate) fn encode_ref<V: Dump>(value: Option<&V>) -> Result<Vec<u8>, Error> {
     let mut dumper = Dumper::new(Vec::with_capacity(128));
-    V::dump_option(value.as_ref(), &mut dumper)?;
+    V::dump_option(value, &mut dumper)?;
     Ok(dumper.end())
 }
 
@@ -129,7 +145,7 @@ impl<W: Write<u8>> Dumper<W> {
                 self.write_byte(0xDD);
                 self.write_array::<4>(len.to_le_bytes());
             },
-            (len @ 0 ..= 0x001F_FFFF, Some(logsize @ 0 ..= 7)) => {
+            (len @ 0 ..= MAX_MIXED_ARRAY_LEN, Some(logsize @ 0 ..= 7)) => {
                 let has_array = len > 0;
                 self.write_byte(0x80 | u8::from(has_array) | (logsize << 1));
                 if has_array {
@@ -137,7 +153,7 @@ impl<W: Write<u8>> Dumper<W> {
                 }
                 self.write_ext_uint(table.assoc_last_free());
             },
-            (len @ 0 ..= 0x001F_FFFF, Some(logsize @ 8 ..= 20)) => {
+            (len @ 0 ..= MAX_MIXED_ARRAY_LEN, Some(logsize @ 8 ..= constants::MAX_ASSOC_LOGLEN)) => {
                 self.write_byte(0xDE);
                 let has_array = len > 0;
                 self.write_byte(u8::from(has_array) | (logsize << 1));
@@ -147,7 +163,7 @@ impl<W: Write<u8>> Dumper<W> {
                 }
                 self.write_ext_uint(table.assoc_last_free());
             },
-            (0x0020_0000 ..= u32::MAX, Some(_)) |
+            (self::EXCEEDED_MIXED_ARRAY_LEN ..= u32::MAX, Some(_)) |
             (_, Some(self::EXCEEDED_LOGLEN ..= LogSize::MAX)) =>
                 return Err(Error::from("unsupported table size")),
         }
@@ -231,21 +247,41 @@ impl<W: Write<u8>> DumperTr for &mut Dumper<W> {
         Ok(())
     }
 
+    fn dump_i64(self, value: i64) -> Result<Self::Ok, Error> {
+        #![allow( clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            reason = "the conversion is checked to be exact" )]
+        if let Ok(value) = i32::try_from(value) {
+            return <Self as DumperTr>::dump_integer(self, value);
+        }
+        let float = value as f64;
+        // `i64::MAX` rounds up to `2 ** 63`, which converts back saturated
+        if float >= 2_f64.powi(63) || float as i64 != value {
+            return Err(Error::from(
+                "integer value should be exactly representable as a float" ));
+        }
+        self.dump_float(float)
+    }
+
     fn dump_string(self, value: &str) -> Result<Self::Ok, Error> {
+        self.dump_bytes(value.as_bytes())
+    }
+
+    fn dump_bytes(self, value: &[u8]) -> Result<Self::Ok, Error> {
         match value.len() {
             0 ..= 0x1F => {
                 self.write_byte(0xA0 | (value.len() as u8));
-                self.write_slice(value.as_bytes());
+                self.write_slice(value);
             },
             0x20 ..= 0xFF => {
                 self.write_byte(0xD9);
                 self.write_byte(value.len() as u8);
-                self.write_slice(value.as_bytes());
+                self.write_slice(value);
             },
-            0x_0100 ..= 0x_FFFF => {
+            0x_0100 ..= MAX_STRING_LEN => {
                 self.write_byte(0xDA);
                 self.write_array::<2>((value.len() as u16).to_le_bytes());
-                self.write_slice(value.as_bytes());
+                self.write_slice(value);
             },
             _ => return Err(Error::from("too long string")),
         }
@@ -258,9 +294,10 @@ impl<W: Write<u8>> DumperTr for &mut Dumper<W> {
         T::Key: KeyDump,
         T::Value: Dump,
     {
+        // the header rejects unsupported sizes, so `iexp2` is safe after it
+        self.dump_table_header(&table)?;
         let mut array_len = table.array_len();
         let mut assoc_len = iexp2(table.assoc_loglen());
-        self.dump_table_header(&table)?;
         let mut serial = SerialWriter::new(self);
         for item in table {
             match (&item, array_len > 0, assoc_len > 0) {
@@ -366,3 +403,26 @@ where W: Write<u8>, K: KeyDump, V: Dump
     }
 }
 
+
+#[cfg(test)]
+mod test {
+
+use crate::{
+    value::Value,
+    Exchange,
+};
+
+use super::dump_blueprint_to_bytes;
+
+#[test]
+fn test_dump_blueprint_to_bytes() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let value = crate::loader::load_blueprint::<Value, Value, crate::error::LoadError>(
+        exchange ).unwrap();
+    let Exchange::Behavior(bytes) = dump_blueprint_to_bytes(value.clone()).unwrap()
+        else { panic!("should be a behavior") };
+    let decoded = crate::loader::decode::<Value>(&bytes).unwrap();
+    assert!(Exchange::Behavior(decoded) == value);
+}
+
+}
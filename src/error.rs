@@ -33,7 +33,16 @@ impl From<String> for LoadError {
 error_from_error!(LoadError: <- crate::common::ascii::AsciiError);
 error_from_error!(LoadError: <- crate::common::intlim::IntLimError);
 error_from_error!(LoadError: <- std::str::Utf8Error);
+error_from_error!(LoadError: <- std::num::TryFromIntError);
 error_from_error!(LoadError: <- std::io::Error);
+error_from_error!(LoadError: <- Box<dyn std::error::Error>);
+error_from_error!(LoadError: <- Box<dyn std::error::Error + Send + Sync>);
+
+impl From<LoadError> for std::io::Error {
+    fn from(value: LoadError) -> Self {
+        Self::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
 
 
 #[derive(Debug, Error)]
@@ -56,3 +65,33 @@ impl From<String> for DumpError {
     }
 }
 
+impl From<DumpError> for std::io::Error {
+    fn from(value: DumpError) -> Self {
+        Self::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+
+#[derive(Debug, Error)]
+#[error("Migration error: {reason}")]
+pub struct MigrationError {
+    reason: String,
+}
+
+impl From<&str> for MigrationError {
+    fn from(reason: &str) -> Self {
+        Self { reason: String::from(reason) }
+    }
+}
+
+impl From<String> for MigrationError {
+    fn from(reason: String) -> Self {
+        Self { reason }
+    }
+}
+
+
+#[derive(Debug, Error)]
+#[error("Exchange is not of the expected kind")]
+#[non_exhaustive]
+pub struct KindMismatch;
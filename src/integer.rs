@@ -0,0 +1,133 @@
+//! `Load` and `Dump` for integer types other than `i32`,
+//! with range-checked coercions.
+//!
+//! The format only has `i32` integers and `f64` floats,
+//! so values outside of the `i32` range are dumped as floats
+//! (see `Dumper::dump_i64`).
+//! This is exact for `u32`; `i64` values that a float cannot represent
+//! exactly are rejected by the exchange format dumper.
+//! Floats with integral values are accepted on load.
+
+use crate::{
+    dump::{Dump, Dumper},
+    load::{Builder, Error, Load, Loader, TableLoader},
+    value::{Key, Value},
+};
+
+struct IntegerBuilder<T>(std::marker::PhantomData<T>);
+
+impl<T> IntegerBuilder<T> {
+    fn new() -> Self { Self(std::marker::PhantomData) }
+}
+
+#[cold]
+fn err_not_integer<E: Error>() -> E {
+    E::from("value should be an integer")
+}
+
+#[cold]
+fn err_out_of_range<E: Error>() -> E {
+    E::from("integer value is out of range")
+}
+
+// `$upper` is `$type::MAX + 1`, the smallest float out of range.
+macro_rules! impl_integer {
+    ($type:ty, $upper:expr) => {
+        impl Load for $type {
+            fn load<L: Loader>(loader: L) -> Result<Option<Self>, L::Error> {
+                loader.load_value(IntegerBuilder::<$type>::new())
+            }
+        }
+
+        impl Builder for IntegerBuilder<$type> {
+            type Output = $type;
+            type Key = Key;
+            type Value = Value;
+            fn build_boolean<E: Error>(self, _: bool) -> Result<Option<$type>, E> {
+                Err(err_not_integer())
+            }
+            fn build_integer<E: Error>(self, value: i32) -> Result<Option<$type>, E> {
+                <$type>::try_from(value)
+                    .map(Some).map_err(|_error| err_out_of_range())
+            }
+            fn build_float<E: Error>(self, value: f64) -> Result<Option<$type>, E> {
+                #![allow( clippy::cast_precision_loss, clippy::cast_lossless,
+                    clippy::cast_possible_truncation, clippy::cast_sign_loss,
+                    reason = "the value is checked to be integral and in range" )]
+                if value.fract() != 0.0 {
+                    return Err(err_not_integer());
+                }
+                if !(<$type>::MIN as f64 .. $upper).contains(&value) {
+                    return Err(err_out_of_range());
+                }
+                Ok(Some(value as $type))
+            }
+            fn build_string<E: Error>(self, _: &str) -> Result<Option<$type>, E> {
+                Err(err_not_integer())
+            }
+            fn build_table<T>(self, _: T) -> Result<Option<$type>, T::Error>
+            where
+                T: TableLoader<Key=Key, Value=Value>,
+                T::Error : Error,
+            {
+                Err(err_not_integer())
+            }
+        }
+
+        impl Dump for $type {
+            fn dump<D: Dumper>(&self, dumper: D) -> Result<D::Ok, D::Error> {
+                dumper.dump_i64(i64::from(*self))
+            }
+        }
+    };
+}
+
+impl_integer!(i64, 2_f64.powi(63));
+impl_integer!(u32, 2_f64.powi(32));
+
+#[cfg(test)]
+mod test {
+
+use crate::codec::WireFormat;
+
+#[test]
+fn test_integer_coercions() {
+    for value in [0_i64, -5, i64::from(i32::MAX) + 1, -(1_i64 << 40)] {
+        assert_eq!(i64::decode(&value.encode().unwrap()).unwrap(), value);
+    }
+    for value in [0_u32, 7, u32::MAX] {
+        assert_eq!(u32::decode(&value.encode().unwrap()).unwrap(), value);
+    }
+    let Err(error) = u32::decode(&(-1_i64).encode().unwrap())
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("out of range"), "{error}");
+    let Err(error) = u32::decode(&(1_i64 << 40).encode().unwrap())
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("out of range"), "{error}");
+}
+
+#[test]
+fn test_integer_float_boundaries() {
+    use crate::value::Value;
+    let float = |value: f64| Value::Float(value).encode().unwrap();
+    assert_eq!(i64::decode(&float(-2_f64.powi(63))).unwrap(), i64::MIN);
+    // the largest float below 2 ** 63
+    assert_eq!( i64::decode(&float(2_f64.powi(63) - 1024.0)).unwrap(),
+        i64::MAX - 1023 );
+    let Err(error) = i64::decode(&float(2_f64.powi(63)))
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("out of range"), "{error}");
+    assert_eq!(u32::decode(&float(4_294_967_295.0)).unwrap(), u32::MAX);
+    let Err(error) = u32::decode(&float(2_f64.powi(32)))
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("out of range"), "{error}");
+    for value in [1_i64 << 53, 1 << 60, i64::MIN] {
+        assert_eq!(i64::decode(&value.encode().unwrap()).unwrap(), value);
+    }
+    for value in [(1_i64 << 53) + 1, i64::MAX, i64::MIN + 1] {
+        let Err(error) = value.encode() else { panic!("should be an error") };
+        assert!(error.to_string().contains("exactly representable"), "{error}");
+    }
+}
+
+}
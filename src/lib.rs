@@ -205,20 +205,21 @@ pub mod table_iter;
 
 pub mod load;
 pub mod dump;
+pub mod codec;
+mod integer;
 
 pub mod dumper;
 pub mod loader;
 pub mod value;
 
 pub mod blueprint;
+pub mod migration;
 
 pub mod de;
 pub mod ser;
 
 mod test;
 
-const MAX_ASSOC_LOGLEN: u8 = 20;
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[allow(clippy::exhaustive_enums)]
 pub enum Exchange<Blueprint, Behavior = Blueprint> {
@@ -233,6 +234,20 @@ impl<P, H> Exchange<P, H> {
             Self::Behavior (value) => Exchange::Behavior (value),
         }
     }
+    /// `AsRef` cannot be implemented for both variants
+    /// (they may have the same type), so this is a fallible accessor.
+    pub fn try_as_blueprint(&self) -> Result<&P, error::KindMismatch> {
+        match self {
+            Self::Blueprint(value) => Ok(value),
+            Self::Behavior(_) => Err(error::KindMismatch),
+        }
+    }
+    pub fn try_as_behavior(&self) -> Result<&H, error::KindMismatch> {
+        match self {
+            Self::Behavior(value) => Ok(value),
+            Self::Blueprint(_) => Err(error::KindMismatch),
+        }
+    }
     pub fn as_deref(&self) -> Exchange<&P::Target, &H::Target>
     where P: std::ops::Deref, H: std::ops::Deref
     {
@@ -269,6 +284,10 @@ impl<V> Exchange<V> {
 }
 
 impl Exchange<()> {
+    /// All kinds of exchange strings.
+    pub fn iter() -> impl Iterator<Item=Self> {
+        [Self::Blueprint(()), Self::Behavior(())].into_iter()
+    }
     pub fn with_value<V>(self, value: V) -> Exchange<V> {
         match self {
             Self::Blueprint(()) => Exchange::Blueprint(value),
@@ -1,8 +1,17 @@
 //! A specialized imitation of `serde::ser`.
 
-use crate::table_iter::{TableItem, TableSize};
+use std::{collections::HashMap, marker::PhantomData};
 
-pub trait Error : std::error::Error + for<'s> From<&'s str> {}
+use crate::{
+    table_iter::{TableItem, AssocItem, TableSize},
+    value::{Key, Value},
+};
+
+/// `Send + Sync + 'static`, so that errors can be boxed
+/// or passed between threads.
+pub trait Error :
+    std::error::Error + for<'s> From<&'s str> + Send + Sync + 'static
+{}
 
 pub trait KeyLoad : Sized {
     fn load_key<L: Loader>(loader: L) -> Result<Option<Self>, L::Error>;
@@ -16,6 +25,12 @@ pub trait KeyBuilder : Sized {
     type Output;
     fn build_integer<E: Error>(self, value: i32) -> Result<Self::Output, E>;
     fn build_string<E: Error>(self, value: &str) -> Result<Self::Output, E>;
+    /// Reserved for symbol keys, should the format ever distinguish
+    /// them from string keys; no loader calls this yet.
+    /// Builds a string key by default.
+    fn build_symbol<E: Error>(self, value: &str) -> Result<Self::Output, E> {
+        self.build_string(value)
+    }
 }
 
 pub trait Builder : Sized {
@@ -29,6 +44,14 @@ pub trait Builder : Sized {
     fn build_integer<E: Error>(self, value: i32) -> Result<Option<Self::Output>, E>;
     fn build_float<E: Error>(self, value: f64) -> Result<Option<Self::Output>, E>;
     fn build_string<E: Error>(self, value: &str) -> Result<Option<Self::Output>, E>;
+    /// Called for strings that are not valid UTF-8.
+    /// (Lua strings are byte sequences, and so can be these.)
+    fn build_bytes<E: Error>(self, value: &[u8]) -> Result<Option<Self::Output>, E> {
+        match std::str::from_utf8(value) {
+            Ok(value) => self.build_string(value),
+            Err(_) => Err(E::from("string should be valid UTF-8")),
+        }
+    }
     fn build_table<T>(self, items: T) -> Result<Option<Self::Output>, T::Error>
     where
         T: TableLoader<Key=Self::Key, Value=Self::Value>,
@@ -43,6 +66,58 @@ pub trait Loader {
     fn load_key<KB: KeyBuilder>( self,
         builder: KB,
     ) -> Result<Option<KB::Output>, Self::Error>;
+    /// Load a table into a struct, field by field.
+    /// Keys not listed in `S::fields()` are skipped.
+    fn load_struct<S: LoadStruct>(self) -> Result<S, Self::Error>
+    where Self: Sized
+    {
+        self.load_value(StructBuilder::<S>(PhantomData))?
+            .ok_or_else(|| Self::Error::from("struct should not be nil"))
+    }
+}
+
+/// Types that load from the named fields of a table,
+/// without going through a `Builder` of their own.
+pub trait LoadStruct : Default {
+    /// Names of the keys to be passed to `set_field`.
+    fn fields() -> &'static [&'static str];
+    fn set_field<E: Error>(&mut self, name: &str, value: Value) -> Result<(), E>;
+}
+
+struct StructBuilder<S>(PhantomData<S>);
+
+impl<S: LoadStruct> Builder for StructBuilder<S> {
+    type Output = S;
+    type Key = Key;
+    type Value = Value;
+    fn build_boolean<E: Error>(self, _: bool) -> Result<Option<S>, E> {
+        Err(E::from("struct should be a table"))
+    }
+    fn build_integer<E: Error>(self, _: i32) -> Result<Option<S>, E> {
+        Err(E::from("struct should be a table"))
+    }
+    fn build_float<E: Error>(self, _: f64) -> Result<Option<S>, E> {
+        Err(E::from("struct should be a table"))
+    }
+    fn build_string<E: Error>(self, _: &str) -> Result<Option<S>, E> {
+        Err(E::from("struct should be a table"))
+    }
+    fn build_table<T>(self, items: T) -> Result<Option<S>, T::Error>
+    where
+        T: TableLoader<Key=Key, Value=Value>,
+        T::Error : Error,
+    {
+        let mut output = S::default();
+        for item in items {
+            let Some(TableItem::Assoc(AssocItem::Live {
+                key: Key::Name(name), value: Some(value), ..
+            })) = item? else { continue };
+            if S::fields().contains(&&*name) {
+                output.set_field::<T::Error>(&name, value)?;
+            }
+        }
+        Ok(Some(output))
+    }
 }
 
 pub trait TableLoader : TableSize + Iterator<
@@ -51,5 +126,164 @@ pub trait TableLoader : TableSize + Iterator<
     type Key : KeyLoad;
     type Value : Load;
     type Error : Error;
+
+    /// Load all non-nil items into a map.
+    /// Array items get integer keys starting with 1.
+    fn collect_into_map(self)
+    -> Result<HashMap<Self::Key, Self::Value>, Self::Error>
+    where
+        Self: Sized,
+        Self::Key: From<i32> + Eq + std::hash::Hash,
+    {
+        let array_len = self.array_len();
+        // the bound is not validated yet, so do not trust it too much
+        let mut map = HashMap::with_capacity(usize::min(self.max_live(), 1 << 16));
+        for (index, item) in (1_u32 ..).zip(self) {
+            match item? {
+                Some(TableItem::Array(value)) => {
+                    if index > array_len {
+                        return Err(Self::Error::from(
+                            "array item should be in the array part" ));
+                    }
+                    let index = i32::try_from(index)
+                        .map_err(|_error| Self::Error::from(
+                            "array index is too large" ))?;
+                    map.insert(Self::Key::from(index), value);
+                },
+                Some(TableItem::Assoc(AssocItem::Live {
+                    key, value: Some(value), ..
+                })) => { map.insert(key, value); },
+                Some(TableItem::Assoc(_)) | None => (),
+            }
+        }
+        Ok(map)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+use std::collections::HashMap;
+
+use crate::{
+    error::LoadError,
+    value::{Key, Value},
+    Exchange,
+};
+
+use super::{Builder, Error, KeyBuilder, Load, LoadStruct, Loader, TableLoader};
+
+struct Map(HashMap<Key, Value>);
+
+struct MapBuilder;
+
+impl Load for Map {
+    fn load<L: Loader>(loader: L) -> Result<Option<Self>, L::Error> {
+        loader.load_value(MapBuilder)
+    }
 }
 
+impl Builder for MapBuilder {
+    type Output = Map;
+    type Key = Key;
+    type Value = Value;
+    fn build_boolean<E: Error>(self, _: bool) -> Result<Option<Map>, E> {
+        Err(E::from("should be a table"))
+    }
+    fn build_integer<E: Error>(self, _: i32) -> Result<Option<Map>, E> {
+        Err(E::from("should be a table"))
+    }
+    fn build_float<E: Error>(self, _: f64) -> Result<Option<Map>, E> {
+        Err(E::from("should be a table"))
+    }
+    fn build_string<E: Error>(self, _: &str) -> Result<Option<Map>, E> {
+        Err(E::from("should be a table"))
+    }
+    fn build_table<T>(self, items: T) -> Result<Option<Map>, T::Error>
+    where
+        T: TableLoader<Key=Key, Value=Value>,
+        T::Error : Error,
+    {
+        Ok(Some(Map(items.collect_into_map()?)))
+    }
+}
+
+#[test]
+fn test_collect_into_map() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let Exchange::Behavior(Some(Map(map))) =
+        crate::loader::load_blueprint::<Map, Map, LoadError>(exchange).unwrap()
+        else { panic!("should be a behavior") };
+    let table = crate::test::behavior_2_table();
+    assert_eq!(map.len(), table.len());
+    for (key, value) in &table {
+        assert!(map.get(&key) == Some(value));
+    }
+}
+
+#[test]
+fn test_build_symbol() {
+    struct StringKeyBuilder;
+    impl KeyBuilder for StringKeyBuilder {
+        type Output = String;
+        fn build_integer<E: Error>(self, value: i32) -> Result<String, E> {
+            Ok(value.to_string())
+        }
+        fn build_string<E: Error>(self, value: &str) -> Result<String, E> {
+            Ok(value.to_owned())
+        }
+    }
+    assert_eq!(
+        StringKeyBuilder.build_symbol::<LoadError>("name").unwrap(),
+        "name" );
+}
+
+#[derive(Default)]
+struct Header {
+    name: Option<Value>,
+    fields_set: usize,
+}
+
+impl LoadStruct for Header {
+    fn fields() -> &'static [&'static str] {
+        &["name", "desc"]
+    }
+    fn set_field<E: Error>(&mut self, name: &str, value: Value) -> Result<(), E> {
+        if name == "name" { self.name = Some(value); }
+        self.fields_set += 1;
+        Ok(())
+    }
+}
+
+impl Load for Header {
+    fn load<L: Loader>(loader: L) -> Result<Option<Self>, L::Error> {
+        loader.load_struct().map(Some)
+    }
+}
+
+#[test]
+fn test_load_struct() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let Exchange::Behavior(Some(header)) =
+        crate::loader::load_blueprint::<Header, Header, LoadError>(exchange).unwrap()
+        else { panic!("should be a behavior") };
+    let table = crate::test::behavior_2_table();
+    assert!(header.name.as_ref() == table.get(&Key::from("name")));
+    let expected_fields = ["name", "desc"].into_iter()
+        .filter(|&name| table.get(&Key::from(name)).is_some())
+        .count();
+    assert_eq!(header.fields_set, expected_fields);
+}
+
+#[test]
+fn test_error_send_sync() {
+    fn boxed<E: Error>(error: E) -> Box<dyn std::error::Error + Send + Sync> {
+        Box::new(error)
+    }
+    let error = boxed(LoadError::from("error"));
+    let message = std::thread::spawn(move || error.to_string()).join().unwrap();
+    assert!(message.contains("error"));
+}
+
+}
@@ -1,46 +1,258 @@
+use std::{
+    io::Read as _,
+    time::Instant,
+};
+
 use flate2::read::ZlibDecoder as UnZippingReader;
 
 use crate::{
     error::LoadError as Error,
     common::{
         ascii::{Ascii, AsciiStr},
-        byteseq::Read,
+        byteseq::{Read, IoRead},
         intlim::{Int62, Int31, decode_base62, Base62Decode},
     },
     Exchange,
 };
 
+use super::check_deadline;
+
 #[cold]
 fn error_eof() -> Error {
     Error::from("unexpected end of data")
 }
 
+#[cold]
+fn error_too_large(len: usize, max_bytes: usize) -> Error {
+    Error::from(format!(
+        "decompressed data would be {len} bytes, \
+        larger than the limit of {max_bytes} bytes" ))
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecompressOptions {
+    /// Limit on the size of the decompressed data
+    pub(crate) max_bytes: Option<usize>,
+    /// Time to give up at, checked between chunks of data
+    pub(crate) deadline: Option<Instant>,
+}
+
+/// Amount of data processed between checks of the deadline
+const DEADLINE_CHUNK_LEN: usize = 0x1_0000;
+
 pub(crate) fn decompress(
     body: &str,
 ) -> Result<Exchange<Vec<u8>>, Error> {
-    let mut body: &[Ascii] = <&AsciiStr>::try_from(body)?;
-    let kind = match body.read_slice(3)
-        .map(|s| <&AsciiStr>::from(s).into())
-        .ok_or_else(error_eof)?
-    {
+    decompress_with_options(body, &DecompressOptions::default())
+}
+
+pub(crate) fn decompress_with_options(
+    body: &str,
+    options: &DecompressOptions,
+) -> Result<Exchange<Vec<u8>>, Error> {
+    decompress_ascii(<&AsciiStr>::try_from(body)?, options)
+}
+
+/// Same as `decompress`, but without requiring UTF-8 first.
+pub(crate) fn decompress_bytes(
+    body: &[u8],
+) -> Result<Exchange<Vec<u8>>, Error> {
+    let ascii_body = <&AsciiStr>::try_from(body).map_err(|_error| {
+        match body.iter().enumerate().find(|&(_, byte)| !byte.is_ascii()) {
+            Some((offset, byte)) => Error::from(format!(
+                "non-ASCII byte {byte:#04X} at offset {offset}" )),
+            None => Error::from("blueprint should be ASCII"),
+        }
+    })?;
+    decompress_ascii(ascii_body, &DecompressOptions::default())
+}
+
+fn decompress_ascii(
+    mut body: &[Ascii],
+    options: &DecompressOptions,
+) -> Result<Exchange<Vec<u8>>, Error> {
+    let kind = read_kind(
+        body.read_array().ok_or_else(error_eof)?
+    )?;
+    let encoded_len = read_len_base31(&mut body)?;
+    let encoded_checksum = body.read_end_byte()
+        .ok_or_else(error_eof)?;
+    let mut decoder = Base62Decode::new(Vec::new(), std::num::Wrapping(0));
+    for chunk in body.chunks(DEADLINE_CHUNK_LEN) {
+        check_deadline(options.deadline)?;
+        decoder.write_slice(chunk)?;
+    }
+    finish(kind, encoded_len, decoder, encoded_checksum, options)
+}
+
+/// Recover an error that was wrapped to pass through `io::Read`.
+pub(crate) fn error_from_io(error: std::io::Error) -> Error {
+    match error.downcast::<Error>() {
+        Ok(error) => error,
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof
+            => error_eof(),
+        Err(error) => Error::from(error),
+    }
+}
+
+/// Same as `decompress`, but read the encoded blueprint from `reader`.
+///
+/// Only the header is read here. Compressed data is decoded
+/// and unzipped as it is read from the returned body.
+/// Uncompressed data does not declare its length,
+/// so it is collected (and its checksum checked) right away.
+pub(crate) fn decompress_read<R: std::io::Read>(
+    mut reader: R,
+) -> Result<(Exchange<()>, ReadBody<R>), Error> {
+    let mut read_ascii_byte = || -> Result<Ascii, Error> {
+        let mut byte = [0];
+        reader.read_exact(&mut byte).map_err(error_from_io)?;
+        Ok(Ascii::try_from(byte[0])?)
+    };
+    let kind = read_kind([
+        read_ascii_byte()?, read_ascii_byte()?, read_ascii_byte()?,
+    ])?;
+    // the length ends with the first digit that is not base31
+    let mut len_digits = Vec::new();
+    loop {
+        let digit = read_ascii_byte()?;
+        len_digits.push(digit);
+        if decode_base62(digit)?.try_as_31().is_err() { break; }
+    }
+    let encoded_len = read_len_base31(len_digits.as_slice())?;
+    let mut decoder = Base62Reader::new(reader);
+    if encoded_len == 0 {
+        let mut body = Vec::new();
+        decoder.read_to_end(&mut body).map_err(error_from_io)?;
+        decoder.finish()?;
+        return Ok((kind, ReadBody::Plain(body)));
+    }
+    // one more byte than declared, to notice that the length is lying
+    let limit = u64::try_from(encoded_len).unwrap_or(u64::MAX).saturating_add(1);
+    let unzipper = UnZippingReader::new(decoder).take(limit);
+    Ok((kind, ReadBody::Zipped(ZippedBody {
+        data: IoRead::new(unzipper, encoded_len),
+        encoded_len,
+    })))
+}
+
+pub(crate) enum ReadBody<R> {
+    Plain(Vec<u8>),
+    Zipped(ZippedBody<R>),
+}
+
+pub(crate) struct ZippedBody<R> {
+    data: IoRead<std::io::Take<UnZippingReader<Base62Reader<R>>>>,
+    encoded_len: usize,
+}
+
+impl<R: std::io::Read> ZippedBody<R> {
+    pub(crate) fn data(&mut self) -> &mut impl Read<u8> {
+        &mut self.data
+    }
+    /// Read the rest of the body and check its length and checksum.
+    ///
+    /// The checksum is at the very end, so corrupted data
+    /// may have failed to load before it could be checked;
+    /// this error replaces the loading error then.
+    pub(crate) fn finish<T>(self, loaded: Result<T, Error>) -> Result<T, Error> {
+        let (unzipper, len) = self.data.finish().map_err(error_from_io)?;
+        unzipper.into_inner().into_inner().finish()?;
+        if len != self.encoded_len {
+            return Err(Error::from("length does not match"));
+        }
+        loaded
+    }
+}
+
+/// Decoder of base62 text that is pulled through `io::Read`.
+///
+/// The last character of the text is the checksum,
+/// so one character is always held back from decoding.
+pub(crate) struct Base62Reader<R> {
+    reader: R,
+    decoder: Base62Decode<Vec<u8>, std::num::Wrapping<u32>>,
+    /// Offset of the first decoded byte not yet read
+    position: usize,
+    pending: Option<Ascii>,
+    ended: bool,
+}
+
+impl<R: std::io::Read> Base62Reader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoder: Base62Decode::new(Vec::new(), std::num::Wrapping(0)),
+            position: 0, pending: None, ended: false,
+        }
+    }
+    /// Read the rest of the text and check the checksum.
+    fn finish(mut self) -> Result<(), Error> {
+        std::io::copy(&mut self, &mut std::io::sink()).map_err(error_from_io)?;
+        let encoded_checksum = self.pending.ok_or_else(error_eof)?;
+        let (_, checksum) = self.decoder.end()?;
+        check_checksum(checksum, encoded_checksum)
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for Base62Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let decoded = self.decoder.writer_mut();
+            if self.position < decoded.len() || self.ended {
+                let len = usize::min(buf.len(), decoded.len() - self.position);
+                buf[..len].copy_from_slice(&decoded[self.position..][..len]);
+                self.position += len;
+                if self.position == decoded.len() {
+                    decoded.clear();
+                    self.position = 0;
+                }
+                return Ok(len);
+            }
+            let mut buffer = [0; 4096];
+            let len = self.reader.read(&mut buffer)?;
+            if len == 0 {
+                self.decoder.end_word()?;
+                self.ended = true;
+                continue;
+            }
+            let chunk: &[Ascii] = <&AsciiStr>::try_from(&buffer[..len])
+                .map_err(Error::from)?;
+            let Some((&last, chunk)) = chunk.split_last() else { continue };
+            if let Some(pending) = self.pending.replace(last) {
+                self.decoder.write_slice(&[pending])?;
+            }
+            self.decoder.write_slice(chunk)?;
+        }
+    }
+}
+
+fn read_kind(prefix: [Ascii; 3]) -> Result<Exchange<()>, Error> {
+    Ok(match <&AsciiStr>::from(prefix.as_slice()).into() {
         "DSB" => Exchange::Blueprint(()),
         "DSC" => Exchange::Behavior(()),
         _ => return Err(Error::from("unrecognized blueprint header")),
-    };
-    let encoded_len = read_len_base31(&mut body)?;
-    let encoded_checksum = decode_base62(
-        body.read_end_byte()
-            .ok_or_else(error_eof)?
-    )?;
-    let mut decoder = Base62Decode::new(Vec::new(), std::num::Wrapping(0));
-    decoder.write_slice(body)?;
-    #[allow(clippy::shadow_unrelated)]
+    })
+}
+
+fn finish(
+    kind: Exchange<()>,
+    encoded_len: usize,
+    decoder: Base62Decode<Vec<u8>, std::num::Wrapping<u32>>,
+    encoded_checksum: Ascii,
+    options: &DecompressOptions,
+) -> Result<Exchange<Vec<u8>>, Error> {
     let (body, checksum) = decoder.end()?;
-    if Int62::divrem(checksum.0).1 != encoded_checksum {
-        return Err(Error::from("checksum does not match"));
+    check_checksum(checksum, encoded_checksum)?;
+    let max_bytes = options.max_bytes.unwrap_or(usize::MAX);
+    // check the declared length first, to fail early
+    let declared_len = if encoded_len == 0 { body.len() } else { encoded_len };
+    if declared_len > max_bytes {
+        return Err(error_too_large(declared_len, max_bytes));
     }
     let body: Vec<u8> = if encoded_len == 0 { body } else {
-        let unzipped = unzip(&body)?;
+        // the declared length may be lying, so also limit the unzipping
+        let unzipped = unzip(&body, max_bytes, options.deadline)?;
         if encoded_len != unzipped.len() {
             return Err(Error::from("length does not match"));
         }
@@ -49,6 +261,14 @@ pub(crate) fn decompress(
     Ok(kind.map_mono(|()| body))
 }
 
+fn check_checksum(checksum: std::num::Wrapping<u32>, encoded_checksum: Ascii)
+-> Result<(), Error> {
+    if Int62::divrem(checksum.0).1 != decode_base62(encoded_checksum)? {
+        return Err(Error::from("checksum does not match"));
+    }
+    Ok(())
+}
+
 fn read_len_base31(mut reader: impl Read<Ascii>) -> Result<usize, Error> {
     const MAX_DIGITS: usize = Int31::u32_sufficient_digits();
     let mut digits = [Int31::zero(); MAX_DIGITS];
@@ -76,13 +296,112 @@ fn read_len_base31(mut reader: impl Read<Ascii>) -> Result<usize, Error> {
         as usize )
 }
 
-fn unzip(data: &[u8]) -> Result<Vec<u8>, Error> {
-    use std::io::Read as _;
-    let mut unzipper = UnZippingReader::new(
+fn unzip(data: &[u8], max_bytes: usize, deadline: Option<Instant>)
+-> Result<Vec<u8>, Error> {
+    let unzipper = UnZippingReader::new(
         data,
     );
+    let limit = u64::try_from(max_bytes).unwrap_or(u64::MAX).saturating_add(1);
+    let mut unzipper = unzipper.take(limit);
     let mut result = Vec::new();
-    unzipper.read_to_end(&mut result)?;
+    loop {
+        check_deadline(deadline)?;
+        let chunk_len = (&mut unzipper).take(DEADLINE_CHUNK_LEN as u64)
+            .read_to_end(&mut result)?;
+        if chunk_len == 0 { break; }
+    }
+    if result.len() > max_bytes {
+        return Err(Error::from(format!(
+            "decompressed data is larger than the limit of {max_bytes} bytes" )));
+    }
     Ok(result)
 }
 
+
+#[cfg(test)]
+mod test {
+
+use crate::common::{
+    ascii::{Ascii, AsciiStr},
+    intlim::{decode_base62, Base62Decode},
+};
+
+use std::time::{Duration, Instant};
+
+use super::{
+    decompress_read, decompress_with_options, DecompressOptions,
+    unzip, Base62Reader,
+};
+
+#[test]
+fn test_unzip_limit() {
+    use std::io::Write as _;
+    let mut encoder = flate2::write::ZlibEncoder::new(
+        Vec::new(), flate2::Compression::default() );
+    encoder.write_all(&[0; 1000]).unwrap();
+    let zipped = encoder.finish().unwrap();
+    assert_eq!(unzip(&zipped, 1000, None).unwrap().len(), 1000);
+    let Err(error) = unzip(&zipped, 999, None)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("limit of 999 bytes"), "{error}");
+}
+
+#[test]
+fn test_decompress_deadline() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let options = DecompressOptions {
+        deadline: Instant::now().checked_add(Duration::from_secs(60)),
+        ..DecompressOptions::default()
+    };
+    decompress_with_options(exchange, &options).unwrap();
+    let past = DecompressOptions {
+        deadline: Instant::now().checked_sub(Duration::from_secs(1)),
+        ..DecompressOptions::default()
+    };
+    let Err(error) = decompress_with_options(exchange, &past)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("too long"), "{error}");
+}
+
+#[test]
+fn test_decompress_read_errors() {
+    struct Failing;
+    impl std::io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+    }
+    let Err(error) = decompress_read(Failing)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("disk on fire"), "{error}");
+    let Err(error) = decompress_read(&b"DS"[..])
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("end of data"), "{error}");
+}
+
+#[test]
+fn test_base62_reader() {
+    use std::{
+    io::Read as _,
+    time::Instant,
+};
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    // skip the kind and the length
+    let text = exchange[3..].trim_start_matches(|c: char| {
+        decode_base62(Ascii::try_from(c as u8).unwrap()).unwrap().try_as_31().is_ok()
+    });
+    let mut reader = Base62Reader::new(&text.as_bytes()[1..]);
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).unwrap();
+    reader.finish().unwrap();
+    let mut decoder = Base62Decode::new(Vec::new(), std::num::Wrapping(0));
+    decoder.write_slice(<&AsciiStr>::try_from(&text[1 .. text.len() - 1]).unwrap()).unwrap();
+    assert_eq!(data, decoder.end().unwrap().0);
+    let mut corrupted = text.as_bytes()[1..].to_vec();
+    *corrupted.last_mut().unwrap() ^= 1;
+    let Err(error) = Base62Reader::new(corrupted.as_slice()).finish()
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("checksum"), "{error}");
+}
+
+}
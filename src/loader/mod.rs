@@ -1,9 +1,12 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
 use crate::{
     error::LoadError as Error,
     common::{
-        u32_to_usize, LogSize, iexp2,
+        try_u32_to_usize, LogSize, iexp2,
         byteseq::Read,
     },
     table_iter::{
@@ -15,28 +18,246 @@ use crate::{
         KeyBuilder, Builder,
         Loader as LoaderTr, TableLoader
     },
+    value::constants,
     Exchange
 };
 
 mod decompress;
 
+mod stream;
+pub use stream::load_blueprint_streaming;
+
 pub fn load_blueprint<P, B, E>(exchange: &str)
 -> Result<Exchange<Option<P>, Option<B>>, Error>
 where P: Load, B: Load,
 {
-    let encoded_data = decompress::decompress(exchange)?;
+    load_blueprint_with_config(exchange, &LoadConfig::default())
+}
+
+/// Limits protecting against adversarial input.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LoadConfig {
+    /// Maximum nesting depth of tables, the outermost table being 1.
+    pub max_depth: usize,
+    /// Maximum size of the decompressed data, if any.
+    pub max_bytes: Option<usize>,
+}
+
+impl LoadConfig {
+    pub const DEFAULT_MAX_DEPTH: usize = 64;
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self { max_depth: Self::DEFAULT_MAX_DEPTH, max_bytes: None }
+    }
+}
+
+pub fn load_blueprint_with_config<P, B>(exchange: &str, config: &LoadConfig)
+-> Result<Exchange<Option<P>, Option<B>>, Error>
+where P: Load, B: Load,
+{
+    let encoded_data = decompress::decompress_with_options(
+        exchange,
+        &decompress::DecompressOptions {
+            max_bytes: config.max_bytes,
+            ..decompress::DecompressOptions::default()
+        },
+    )?;
+    encoded_data.as_deref().map(
+        |data| P::load(&mut Loader::new(data).with_config(config)),
+        |data| B::load(&mut Loader::new(data).with_config(config)),
+    ).transpose()
+}
+
+/// Same as `load_blueprint`, but take the encoded blueprint as bytes
+/// (which should be ASCII), e.g. straight from a network buffer.
+pub fn load_blueprint_bytes<P, B>(data: &[u8])
+-> Result<Exchange<Option<P>, Option<B>>, Error>
+where P: Load, B: Load,
+{
+    let encoded_data = decompress::decompress_bytes(data)?;
     encoded_data.as_deref().map(decode, decode).transpose()
 }
 
-fn decode<V: Load>(data: &[u8]) -> Result<Option<V>, Error>
+/// Same as `load_blueprint`, but read the encoded blueprint
+/// from `reader` as the loading goes, instead of requiring it in memory.
+///
+/// Compressed data is decoded and unzipped in chunks, as it is loaded.
+/// Since the checksum comes last, it is checked only after loading.
+/// Uncompressed data (which the dumper produces when zipping does not help)
+/// does not declare its length upfront, so it is collected before loading.
+pub fn load_blueprint_read<P, B, R>(reader: R)
+-> Result<Exchange<Option<P>, Option<B>>, Error>
+where P: Load, B: Load, R: std::io::Read,
+{
+    let (kind, body) = decompress::decompress_read(reader)?;
+    Ok(match kind {
+        Exchange::Blueprint(()) => Exchange::Blueprint(decode_read(body)?),
+        Exchange::Behavior (()) => Exchange::Behavior (decode_read(body)?),
+    })
+}
+
+fn decode_read<V: Load, R: std::io::Read>(body: decompress::ReadBody<R>)
+-> Result<Option<V>, Error>
+{
+    match body {
+        decompress::ReadBody::Plain(data) => decode(&data),
+        decompress::ReadBody::Zipped(mut body) => {
+            let loaded = V::load(&mut Loader::new(body.data()));
+            body.finish(loaded)
+        },
+    }
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), Error> {
+    match deadline {
+        Some(deadline) if Instant::now() > deadline =>
+            Err(Error::from("loading took too long")),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn decode<V: Load>(data: &[u8]) -> Result<Option<V>, Error>
 {
     V::load(&mut Loader::new(data))
 }
 
+/// Same as `load_blueprint`, but report progress as
+/// `(bytes_processed, total_bytes)` of the decompressed data.
+///
+/// Progress is reported once after decompression, then as the data
+/// is loaded, about every percent of it, and once after loading.
+pub fn load_blueprint_with_callback<P, B, F>(exchange: &str, on_progress: F)
+-> Result<Exchange<Option<P>, Option<B>>, Error>
+where P: Load, B: Load, F: FnMut(u64, u64),
+{
+    let encoded_data = decompress::decompress(exchange)?;
+    let mut reader = ProgressRead::new(
+        encoded_data.as_ref().unwrap().as_slice(), on_progress );
+    let decoded = match encoded_data {
+        Exchange::Blueprint(_) => Exchange::Blueprint(
+            P::load(&mut Loader::new(&mut reader))? ),
+        Exchange::Behavior(_) => Exchange::Behavior(
+            B::load(&mut Loader::new(&mut reader))? ),
+    };
+    reader.finish();
+    Ok(decoded)
+}
+
+/// Reader that reports how much of the data has been read.
+struct ProgressRead<'s, F: FnMut(u64, u64)> {
+    data: &'s [u8],
+    total: u64,
+    reported: u64,
+    next_report: u64,
+    on_progress: F,
+}
+
+impl<'s, F: FnMut(u64, u64)> ProgressRead<'s, F> {
+    fn new(data: &'s [u8], on_progress: F) -> Self {
+        let mut this = Self {
+            data,
+            total: data.len() as u64,
+            reported: 0,
+            next_report: 0,
+            on_progress,
+        };
+        this.report(0);
+        this
+    }
+    fn report(&mut self, processed: u64) {
+        if processed < self.next_report { return; }
+        (self.on_progress)(processed, self.total);
+        self.reported = processed;
+        self.next_report = processed + u64::max(self.total / 100, 1);
+    }
+    /// Report the whole data as processed, even if some of it was not read.
+    fn finish(mut self) {
+        if self.reported < self.total {
+            (self.on_progress)(self.total, self.total);
+        }
+    }
+    /// Report the position after reading `len` more bytes, if there are any.
+    fn advance(&mut self, len: usize) {
+        if let Some(rest_len) = self.data.len().checked_sub(len) {
+            self.report(self.total - rest_len as u64);
+        }
+    }
+}
+
+impl<F: FnMut(u64, u64)> Read<u8> for ProgressRead<'_, F> {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+    fn read_slice(&mut self, len: usize) -> Option<&[u8]> {
+        self.advance(len);
+        self.data.read_slice(len)
+    }
+    fn read_end_slice(&mut self, len: usize) -> Option<&[u8]> {
+        self.advance(len);
+        self.data.read_end_slice(len)
+    }
+    fn read_rest(&mut self) -> &[u8] {
+        self.advance(self.data.len());
+        self.data.read_rest()
+    }
+    fn peek_slice(&self, len: usize) -> &[u8] {
+        self.data.peek_slice(len)
+    }
+}
+
+/// Same as `load_blueprint`, but give up with an error
+/// if loading takes longer than `timeout`.
+///
+/// Loaded values are not `Send`, so the loading cannot be moved
+/// to another thread; instead, the deadline is checked
+/// between chunks of base62 decoding and of unzipping,
+/// and then before each value is loaded.
+pub fn load_blueprint_timeout<P, B>(exchange: &str, timeout: Duration)
+-> Result<Exchange<Option<P>, Option<B>>, Error>
+where P: Load, B: Load,
+{
+    let deadline = Instant::now().checked_add(timeout);
+    let encoded_data = decompress::decompress_with_options(
+        exchange,
+        &decompress::DecompressOptions {
+            deadline,
+            ..decompress::DecompressOptions::default()
+        },
+    )?;
+    encoded_data.as_deref().map(
+        |data| P::load(&mut Loader::new(data).with_deadline(deadline)),
+        |data| B::load(&mut Loader::new(data).with_deadline(deadline)),
+    ).transpose()
+}
+
+/// Read only the header of the root table,
+/// e.g. to decide whether the blueprint is worth loading at all.
+pub fn load_table_header(exchange: &str)
+-> Result<Exchange<TableHeader>, Error>
+{
+    let encoded_data = decompress::decompress(exchange)?;
+    encoded_data.as_deref().map_mono(|data| {
+        let mut loader = Loader::new(data);
+        let head = loader.read_byte()?;
+        match head {
+            0x80 ..= 0x8F | 0x90 ..= 0x9F | 0xDC | 0xDE =>
+                loader.load_table_header(head),
+            _ => Err(Error::from(
+                "Blueprint or behavior should be represented with a table" )),
+        }
+    }).transpose()
+}
+
 
 struct Loader<R: Read<u8>> {
     reader: R,
     max_array_len: u32,
+    deadline: Option<Instant>,
+    depth: usize,
+    max_depth: usize,
 }
 
 #[cold]
@@ -61,12 +282,27 @@ fn error_unsupported_size() -> Error {
         "Table size is unsupported" )
 }
 
-struct TableHeader {
+/// Dimensions of an encoded table, available before its items are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TableHeader {
     array_len: u32,
     assoc_loglen: Option<LogSize>,
     assoc_last_free: u32,
 }
 
+impl TableSize for TableHeader {
+    fn array_len(&self) -> u32 {
+        self.array_len
+    }
+    fn assoc_loglen(&self) -> Option<LogSize> {
+        self.assoc_loglen
+    }
+    fn assoc_last_free(&self) -> u32 {
+        self.assoc_last_free
+    }
+}
+
 impl TableHeader {
     fn array(array_len: u32) -> Self {
         Self {
@@ -89,9 +325,40 @@ impl<R: Read<u8>> Loader<R> {
         Self {
             reader,
             max_array_len,
+            deadline: None,
+            depth: 0,
+            max_depth: LoadConfig::DEFAULT_MAX_DEPTH,
         }
     }
 
+    #[must_use]
+    fn with_config(self, config: &LoadConfig) -> Self {
+        Self { max_depth: config.max_depth, ..self }
+    }
+
+    #[must_use]
+    fn with_deadline(self, deadline: Option<Instant>) -> Self {
+        Self { deadline, ..self }
+    }
+
+    /// Hexadecimal representation of up to `n_bytes` upcoming bytes,
+    /// without consuming them.
+    fn debug_dump(&self, n_bytes: usize) -> String {
+        self.reader.peek_slice(n_bytes).iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>().join(" ")
+    }
+
+    #[cold]
+    fn error_unexpected_head(&self, head: u8) -> Error {
+        Error::from(format!( "unexpected byte {head:X} (followed by [{}])",
+            self.debug_dump(8) ))
+    }
+
+    fn check_deadline(&self) -> Result<(), Error> {
+        check_deadline(self.deadline)
+    }
+
     fn read_byte(&mut self) -> Result<u8, Error> {
         self.reader.read_byte()
             .ok_or_else(error_eof)
@@ -201,7 +468,7 @@ impl<R: Read<u8>> Loader<R> {
 
     fn load_string( &mut self,
         head: u8,
-    ) -> Result<&str, Error> {
+    ) -> Result<&[u8], Error> {
         #![allow(clippy::cast_lossless)]
         let len = match head {
             head @ 0xA0 ..= 0xBF => (head & 0x1F) as u32,
@@ -209,8 +476,8 @@ impl<R: Read<u8>> Loader<R> {
             0xDA => u16::from_le_bytes(self.read_array::<2>()?) as u32,
             _ => return Err(error_unexpected(head)),
         };
-        let len = u32_to_usize(len);
-        Ok(std::str::from_utf8(self.read_slice(len)?)?)
+        let len = try_u32_to_usize(len)?;
+        self.read_slice(len)
     }
 
     fn load_table_header( &mut self,
@@ -270,6 +537,7 @@ impl<R: Read<u8>> LoaderTr for &mut Loader<R> {
     -> Result<Option<B::Output>, Error>
     where B: Builder
     {
+        self.check_deadline()?;
         let head = self.read_byte()?;
         match head {
             0xC0 => {
@@ -286,7 +554,11 @@ impl<R: Read<u8>> LoaderTr for &mut Loader<R> {
             0xCB => builder.build_float(
                 self.load_float(head)? ),
             0xA0 ..= 0xBF | 0xD9 | 0xDA => {
-                builder.build_string(self.load_string(head)?)
+                let value = self.load_string(head)?;
+                match std::str::from_utf8(value) {
+                    Ok(value) => builder.build_string(value),
+                    Err(_) => builder.build_bytes(value),
+                }
             },
             0x80 ..= 0x8F | 0x90 ..= 0x9F | 0xDC | 0xDE => {
                 let TableHeader { array_len, assoc_loglen, assoc_last_free } =
@@ -298,7 +570,7 @@ impl<R: Read<u8>> LoaderTr for &mut Loader<R> {
                     Some(rest) => rest,
                 };
                 if let Some(assoc_loglen) = assoc_loglen {
-                    if assoc_loglen > crate::MAX_ASSOC_LOGLEN {
+                    if assoc_loglen > constants::MAX_ASSOC_LOGLEN {
                         return Err(error_unsupported_size());
                     }
                     self.max_array_len = match
@@ -308,13 +580,20 @@ impl<R: Read<u8>> LoaderTr for &mut Loader<R> {
                         Some(rest) => rest,
                     };
                 }
-                builder.build_table(SerialReader::new(
+                if self.depth >= self.max_depth {
+                    return Err(Error::from(format!(
+                        "tables are nested deeper than {}", self.max_depth )));
+                }
+                self.depth += 1;
+                let output = builder.build_table(SerialReader::new(
                     self,
                     array_len,
                     assoc_loglen, assoc_last_free,
-                ))
+                ));
+                self.depth -= 1;
+                output
             },
-            _ => Err(error_unexpected(head)),
+            _ => Err(self.error_unexpected_head(head)),
         }
     }
 
@@ -331,9 +610,10 @@ impl<R: Read<u8>> LoaderTr for &mut Loader<R> {
                 builder.build_integer::<Error>(self.load_integer(head)?)?
             )),
             0xA0 ..= 0xBF | 0xD9 | 0xDA => Ok(Some(
-                builder.build_string::<Error>(self.load_string(head)?)?
+                builder.build_string::<Error>(
+                    std::str::from_utf8(self.load_string(head)?)? )?
             )),
-            _ => Err(error_unexpected(head)),
+            _ => Err(self.error_unexpected_head(head)),
         }
     }
 
@@ -444,3 +724,145 @@ where R: Read<u8>, K: KeyLoad, V: Load
     type Error = Error;
 }
 
+
+#[cfg(test)]
+mod test {
+
+use std::time::Duration;
+
+use crate::{
+    error::LoadError as Error,
+    table_iter::TableSize as _,
+    value::Value,
+    Exchange,
+};
+
+use super::{
+    Loader, decode,
+    load_blueprint, load_blueprint_bytes, load_blueprint_read,
+    load_blueprint_timeout, load_blueprint_with_config, LoadConfig, load_blueprint_with_callback,
+    load_table_header,
+};
+
+#[test]
+fn test_load_table_header() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let Exchange::Behavior(header) = load_table_header(exchange).unwrap()
+        else { panic!("should be a behavior") };
+    let table = crate::test::behavior_2_table();
+    assert_eq!(
+        u32::try_from(table.array_iter_all().len()).unwrap(),
+        header.array_len() );
+    assert!(header.assoc_loglen().is_some());
+}
+
+#[test]
+fn test_load_blueprint_timeout() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    load_blueprint_timeout::<Value, Value>(exchange, Duration::from_secs(60))
+        .unwrap();
+    let Err(error) = load_blueprint_timeout::<Value, Value>(exchange, Duration::ZERO)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("too long"), "{error}");
+}
+
+#[test]
+fn test_load_blueprint_with_callback() {
+    let mut calls = Vec::new();
+    load_blueprint_with_callback::<Value, Value, _>(
+        crate::test::EXCHANGE_BEHAVIOR_2,
+        |processed, total| calls.push((processed, total)),
+    ).unwrap();
+    let [(0, total), .., (processed, total_again)] = calls[..]
+        else { panic!("should be called at least twice") };
+    assert!(total > 0 && processed == total && total_again == total);
+    assert!(calls.len() > 10, "{calls:?}");
+    assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0), "{calls:?}");
+    assert!(calls.iter().all(|&(_, total_again)| total_again == total));
+}
+
+#[test]
+fn test_debug_dump() {
+    let loader = Loader::new(&[0x01_u8, 0xAB, 0xFF][..]);
+    assert_eq!(loader.debug_dump(2), "01 AB");
+    assert_eq!(loader.debug_dump(10), "01 AB FF");
+    let Err(error) = decode::<Value>(&[0xC1, 0x02, 0x03])
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("[02 03]"));
+}
+
+#[test]
+fn test_load_blueprint_bytes() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let from_bytes = load_blueprint_bytes::<Value, Value>(exchange.as_bytes()).unwrap();
+    let from_str = load_blueprint::<Value, Value, Error>(exchange).unwrap();
+    assert!(from_bytes == from_str);
+    let mut data = exchange.as_bytes().to_vec();
+    data[5] = 0xFF;
+    let Err(error) = load_blueprint_bytes::<Value, Value>(&data)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("offset 5"), "{error}");
+}
+
+#[test]
+fn test_load_blueprint_read() {
+    // yields data in small uneven chunks
+    struct Trickle<'s>(&'s [u8], usize);
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.1 = self.1 % 5 + 1;
+            let len = self.0.len().min(buf.len()).min(self.1);
+            let (chunk, rest) = self.0.split_at(len);
+            buf[..len].copy_from_slice(chunk);
+            self.0 = rest;
+            Ok(len)
+        }
+    }
+    for exchange in [crate::test::EXCHANGE_BEHAVIOR_1_UNIT, crate::test::EXCHANGE_BEHAVIOR_2] {
+        let from_reader = load_blueprint_read::<Value, Value, _>(
+            Trickle(exchange.as_bytes(), 0) ).unwrap();
+        let from_str = load_blueprint::<Value, Value, Error>(exchange).unwrap();
+        assert!(from_reader == from_str);
+    }
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let Err(error) = load_blueprint_read::<Value, Value, _>(
+        &exchange.as_bytes()[..exchange.len() / 2] )
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("end of data"), "{error}");
+    let mut corrupted = exchange.as_bytes().to_vec();
+    *corrupted.last_mut().unwrap() ^= 1;
+    let Err(error) = load_blueprint_read::<Value, Value, _>(corrupted.as_slice())
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("checksum"), "{error}");
+}
+
+#[test]
+fn test_load_max_depth() {
+    let mut value = Value::Integer(1);
+    for _ in 0 .. 10 {
+        value = Value::Table([(crate::value::Key::Index(1), value)].into_iter().collect());
+    }
+    let exchange = crate::dumper::dump_blueprint::<Value, Value>(
+        Exchange::Blueprint(Some(value.clone())) ).unwrap();
+    let config = LoadConfig { max_depth: 10, ..LoadConfig::default() };
+    let loaded = load_blueprint_with_config::<Value, Value>(&exchange, &config).unwrap();
+    assert!(loaded == Exchange::Blueprint(Some(value)));
+    let shallow = LoadConfig { max_depth: 9, ..LoadConfig::default() };
+    let Err(error) = load_blueprint_with_config::<Value, Value>(&exchange, &shallow)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains('9'), "{error}");
+}
+
+#[test]
+fn test_load_max_bytes() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let len = super::decompress::decompress(exchange).unwrap().unwrap().len();
+    let config = LoadConfig { max_bytes: Some(len), ..LoadConfig::default() };
+    load_blueprint_with_config::<Value, Value>(exchange, &config).unwrap();
+    let small = LoadConfig { max_bytes: Some(len - 1), ..LoadConfig::default() };
+    let Err(error) = load_blueprint_with_config::<Value, Value>(exchange, &small)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains(&len.to_string()), "{error}");
+}
+
+}
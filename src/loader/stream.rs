@@ -0,0 +1,119 @@
+use crate::{
+    error::LoadError as Error,
+    table_iter::{TableItem, AssocItem},
+    load::{
+        Error as ErrorTr,
+        Builder, Loader as LoaderTr, TableLoader,
+    },
+    value::{Key, Value},
+    Exchange,
+};
+
+use super::{Loader, decompress};
+
+/// Load a blueprint, calling `callback` for each entry of the root table
+/// instead of collecting them.
+///
+/// Entries are passed in the order of encoding (array part first),
+/// and each value is fully loaded before the callback is called.
+pub fn load_blueprint_streaming<F>(exchange: &str, callback: F)
+-> Result<Exchange<()>, Error>
+where F: FnMut(Key, Value)
+{
+    let encoded_data = decompress::decompress(exchange)?;
+    let kind = encoded_data.as_ref().map_mono(|_| ());
+    let data = encoded_data.unwrap();
+    let loaded = (&mut Loader::new(data.as_slice()))
+        .load_value(StreamBuilder { callback })?;
+    if loaded.is_none() {
+        return Err(Error::from(
+            "Blueprint or behavior should not be represented with nil" ));
+    }
+    Ok(kind)
+}
+
+struct StreamBuilder<F> {
+    callback: F,
+}
+
+#[cold]
+fn err_not_table<E: ErrorTr>() -> E {
+    E::from("Blueprint or behavior should be represented with a table")
+}
+
+impl<F> Builder for StreamBuilder<F>
+where F: FnMut(Key, Value)
+{
+    type Output = ();
+    type Key = Key;
+    type Value = Value;
+
+    fn build_boolean<E: ErrorTr>(self, _: bool) -> Result<Option<()>, E> {
+        Err(err_not_table())
+    }
+
+    fn build_integer<E: ErrorTr>(self, _: i32) -> Result<Option<()>, E> {
+        Err(err_not_table())
+    }
+
+    fn build_float<E: ErrorTr>(self, _: f64) -> Result<Option<()>, E> {
+        Err(err_not_table())
+    }
+
+    fn build_string<E: ErrorTr>(self, _: &str) -> Result<Option<()>, E> {
+        Err(err_not_table())
+    }
+
+    fn build_table<T>(mut self, items: T) -> Result<Option<()>, T::Error>
+    where
+        T: TableLoader<Key=Key, Value=Value>,
+        T::Error : ErrorTr,
+    {
+        let array_len = items.array_len();
+        let mut array_index: u32 = 0;
+        for item in items {
+            let item = item?;
+            if array_index < array_len {
+                array_index += 1;
+                if let Some(TableItem::Array(value)) = item {
+                    let index = i32::try_from(array_index)
+                        .map_err(|_error| T::Error::from(
+                            "array index is too large" ))?;
+                    (self.callback)(Key::Index(index), value);
+                }
+                continue;
+            }
+            if let Some(TableItem::Assoc(AssocItem::Live {
+                key, value: Some(value), ..
+            })) = item {
+                (self.callback)(key, value);
+            }
+        }
+        Ok(Some(()))
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+use crate::{
+    value::Table,
+    Exchange,
+};
+
+use super::load_blueprint_streaming;
+
+#[test]
+fn test_streaming_matches_loading() {
+    let exchange = crate::test::EXCHANGE_BEHAVIOR_2;
+    let mut items = Vec::new();
+    let kind = load_blueprint_streaming(exchange, |key, value| {
+        items.push((key, value));
+    }).unwrap();
+    assert!(matches!(kind, Exchange::Behavior(())));
+    let table = crate::test::behavior_2_table();
+    assert!(items.into_iter().collect::<Table>() == table);
+}
+
+}
@@ -0,0 +1,43 @@
+//! Conversion of blueprint values between format versions.
+//!
+//! Each pair of adjacent versions gets its own type
+//! implementing [`Migration`].
+
+use crate::{
+    error::MigrationError as Error,
+    value::Value,
+};
+
+pub trait Migration {
+    fn migrate(value: Value) -> Result<Value, Error>;
+}
+
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct V1ToV2;
+
+impl Migration for V1ToV2 {
+    fn migrate(_value: Value) -> Result<Value, Error> {
+        Err(Error::from("migration from v1 to v2 is not yet implemented"))
+    }
+}
+
+pub fn migrate_v1_to_v2(value: Value) -> Result<Value, Error> {
+    V1ToV2::migrate(value)
+}
+
+#[cfg(test)]
+mod test {
+
+use crate::value::Value;
+
+use super::migrate_v1_to_v2;
+
+#[test]
+fn test_migrate_v1_to_v2_stub() {
+    let Err(error) = migrate_v1_to_v2(Value::Integer(1))
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("not yet implemented"), "{error}");
+}
+
+}
@@ -241,8 +241,8 @@ impl ser::Serializer for ValueSerializer {
         Ok(Some(Value::String(Str::from(v))))
     }
 
-    fn serialize_bytes(self, _: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Err(Self::Error::custom("bytes are not supported"))
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(Value::Bytes(Vec::from(v))))
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
@@ -4,7 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::common::LogSize;
+use crate::common::{LogSize, iexp2_checked, u32_to_usize};
 
 #[derive(Debug, Clone)]
 #[allow(clippy::exhaustive_enums)]
@@ -20,6 +20,59 @@ pub enum AssocItem<K, V> {
     Live { key: K, value: Option<V>, link: i32 },
 }
 
+impl<K, V> AssocItem<K, V> {
+    #[must_use]
+    #[inline]
+    pub const fn is_live(&self) -> bool {
+        matches!(self, Self::Live { .. })
+    }
+    #[must_use]
+    #[inline]
+    pub const fn is_dead(&self) -> bool {
+        matches!(self, Self::Dead { .. })
+    }
+    /// The value of a live item.
+    /// `None` both for dead items and for live items with nil value.
+    #[must_use]
+    #[inline]
+    pub const fn value(&self) -> Option<&V> {
+        match self {
+            Self::Live { value: Some(value), .. } => Some(value),
+            _ => None,
+        }
+    }
+    /// The key of a live item.
+    #[must_use]
+    #[inline]
+    pub const fn key(&self) -> Option<&K> {
+        match self {
+            Self::Live { key, .. } => Some(key),
+            Self::Dead { .. } => None,
+        }
+    }
+    /// Relative offset of the next item in the hash chain,
+    /// or zero if this item ends the chain.
+    #[must_use]
+    #[inline]
+    pub const fn link(&self) -> i32 {
+        match *self {
+            Self::Live { link, .. } | Self::Dead { link } => link,
+        }
+    }
+}
+
+impl<K, V> AsRef<K> for AssocItem<K, V> {
+    /// # Panics
+    /// Panics if the item is dead.
+    #[track_caller]
+    fn as_ref(&self) -> &K {
+        match self {
+            Self::Live { key, .. } => key,
+            Self::Dead { .. } => panic!("dead item has no key"),
+        }
+    }
+}
+
 impl<K, V> AssocItem<K, V>
 where K: Clone
 {
@@ -68,5 +121,50 @@ pub trait TableSize {
     fn assoc_loglen(&self) -> Option<LogSize>;
     #[must_use]
     fn assoc_last_free(&self) -> u32;
+    /// Upper bound on the number of live items,
+    /// suitable for pre-sizing allocations.
+    #[must_use]
+    fn max_live(&self) -> usize {
+        let assoc_len = iexp2_checked(self.assoc_loglen())
+            .map_or(usize::MAX, u32_to_usize);
+        u32_to_usize(self.array_len()).saturating_add(assoc_len)
+    }
 }
 
+
+#[cfg(test)]
+mod test {
+
+use super::{AssocItem, TableSize};
+
+#[test]
+fn test_assoc_item_accessors() {
+    let live = AssocItem::<&str, i32>::Live { key: "a", value: Some(1), link: 2 };
+    let dead = AssocItem::<&str, i32>::Dead { link: -1 };
+    assert!(live.is_live() && !live.is_dead());
+    assert!(dead.is_dead() && !dead.is_live());
+    assert_eq!(live.value(), Some(&1));
+    assert_eq!(dead.value(), None);
+    let nil = AssocItem::<&str, i32>::Live { key: "b", value: None, link: 0 };
+    assert_eq!(nil.value(), None);
+    assert_eq!(live.key(), Some(&"a"));
+    assert_eq!(dead.key(), None);
+    assert_eq!(live.link(), 2);
+    assert_eq!(dead.link(), -1);
+    assert_eq!(*AsRef::<&str>::as_ref(&live), "a");
+}
+
+#[test]
+fn test_max_live() {
+    struct Size(u32, Option<u8>);
+    impl TableSize for Size {
+        fn array_len(&self) -> u32 { self.0 }
+        fn assoc_loglen(&self) -> Option<u8> { self.1 }
+        fn assoc_last_free(&self) -> u32 { 0 }
+    }
+    assert_eq!(Size(0, None).max_live(), 0);
+    assert_eq!(Size(3, Some(2)).max_live(), 7);
+    assert_eq!(Size(3, Some(u8::MAX)).max_live(), usize::MAX);
+}
+
+}
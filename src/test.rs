@@ -1,5 +1,11 @@
 #![cfg(test)]
 
+use crate::{
+    error::LoadError,
+    value::{Table, Value},
+    Exchange,
+};
+
 pub(crate) const EXCHANGE_BEHAVIOR_1_UNIT: &str = "\
     DSC22y1Z49l21IhQFh0oJ9l64TPfet44myv4377DXE0xACL43XfsVo13Q2e52uEK\
     v80XNctN4RLH2q3jfPpS2AEMU31gVJcw0JF1R03moTTo2DIJVW4VdGXN4DfvLt2J\
@@ -109,3 +115,11 @@ pub(crate) const RON_VALUE_1_COMPACT: &str = const_format::concatcp!(
     "}"
 );
 
+/// `EXCHANGE_BEHAVIOR_2` loaded as a table of values.
+pub(crate) fn behavior_2_table() -> Table {
+    let Exchange::Behavior(Some(Value::Table(table))) =
+        crate::loader::load_blueprint::<Value, Value, LoadError>(EXCHANGE_BEHAVIOR_2)
+        .unwrap()
+        else { panic!("should be a behavior table") };
+    table
+}
@@ -0,0 +1,21 @@
+//! Limits and magic numbers shared by the game's Lua tables
+//! and the exchange string format.
+
+use crate::common::LogSize;
+
+/// Lua arrays start from this index.
+pub const FIRST_INDEX: i32 = 1;
+
+/// Largest base 2 logarithm of the assoc part size
+/// that the format (and this library) supports.
+pub const MAX_ASSOC_LOGLEN: LogSize = 20;
+
+/// Largest length of the array part of a table
+/// that also has an assoc part.
+pub const MAX_MIXED_ARRAY_LEN: u32 = 0x001F_FFFF;
+
+/// Largest length (in bytes) of a string that can be encoded.
+pub const MAX_STRING_LEN: usize = 0x_FFFF;
+
+/// Seed of the Lua string hash, as used by the game.
+pub const STRING_HASH_SEED: u32 = 0x_645D_BFCD;
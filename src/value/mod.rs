@@ -1,9 +1,19 @@
 use crate::Str;
 
+pub mod constants;
+
 mod table;
-pub use table::{ArrayBuilder, TableBuilder};
+pub use table::{
+    ArrayBuilder, TableBuilder,
+    RehashError, SwapError, PushError, FillError, TableConversionError,
+    TableStats, TypeCounts, HashLayout,
+    str_table_hash,
+};
 pub(crate) use table::ArrayIntoIter;
 
+mod schema;
+pub use schema::{Schema, ScalarType};
+
 #[derive( Clone,
     PartialEq, Eq, PartialOrd, Ord, Hash )]
 #[allow(clippy::exhaustive_enums)]
@@ -45,6 +55,13 @@ impl Key {
             None => Str::from(name),
         })
     }
+    /// Parse an integer key written in brackets, like `"[1]"`.
+    /// Returns `None` for anything else, including plain `"1"`.
+    #[must_use]
+    pub fn try_from_str_integer(string: &str) -> Option<Self> {
+        let index = string.strip_prefix('[')?.strip_suffix(']')?;
+        Some(Self::Index(index.parse().ok()?))
+    }
 }
 
 #[inline]
@@ -94,6 +111,12 @@ impl From<&'static str> for Key {
     }
 }
 
+impl From<i32> for Key {
+    fn from(index: i32) -> Self {
+        Self::Index(index)
+    }
+}
+
 impl From<Str> for Key {
     fn from(string: Str) -> Self {
         Self::Name(string)
@@ -112,11 +135,23 @@ pub enum Value {
     Integer(i32),
     Float(f64),
     String(Str),
+    /// A string that is not valid UTF-8.
+    /// Serialized as a map with the single key `"$bytes"`
+    /// holding the bytes in hexadecimal, e.g. `{"$bytes": "61ff"}`.
+    ///
+    /// The encoding is ambiguous: a table whose only entry is `"$bytes"`
+    /// with a hexadecimal string value is deserialized as `Value::Bytes`,
+    /// so such tables do not survive a round trip through serde.
+    Bytes(Vec<u8>),
     Table(Table),
 }
 
 pub type Table = table::Table<Value>;
 
+/// The key of the single-entry map representing `Value::Bytes`
+/// in serde formats.
+const SERDE_BYTES_TAG: &str = "$bytes";
+
 impl std::fmt::Debug for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -124,11 +159,138 @@ impl std::fmt::Debug for Value {
             Self::Integer(value) => value.fmt(f),
             Self::Float  (value) => value.fmt(f),
             Self::String (value) => value.fmt(f),
+            Self::Bytes  (value) => write!(f, "b\"{}\"", value.escape_ascii()),
             Self::Table  (table) => table.fmt(f),
         }
     }
 }
 
+impl Value {
+    #[must_use]
+    #[inline]
+    pub const fn is_bool(&self) -> bool {
+        matches!(self, Self::Boolean(_))
+    }
+    #[must_use]
+    #[inline]
+    pub const fn is_integer(&self) -> bool {
+        matches!(self, Self::Integer(_))
+    }
+    #[must_use]
+    #[inline]
+    pub const fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
+    }
+    /// Only valid UTF-8 strings; see `is_bytes` for the rest.
+    #[must_use]
+    #[inline]
+    pub const fn is_string(&self) -> bool {
+        matches!(self, Self::String(_))
+    }
+    #[must_use]
+    #[inline]
+    pub const fn is_bytes(&self) -> bool {
+        matches!(self, Self::Bytes(_))
+    }
+    #[must_use]
+    #[inline]
+    pub const fn is_table(&self) -> bool {
+        matches!(self, Self::Table(_))
+    }
+    /// Name of the variant, for messages.
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        match *self {
+            Self::Boolean(_) => "boolean",
+            Self::Integer(_) => "integer",
+            Self::Float  (_) => "float",
+            Self::String (_) => "string",
+            Self::Bytes  (_) => "bytes",
+            Self::Table  (_) => "table",
+        }
+    }
+    #[must_use]
+    #[inline]
+    pub fn as_table(&self) -> Option<&Table> {
+        match *self {
+            Self::Table(ref table) => Some(table),
+            _ => None,
+        }
+    }
+    /// Length in the sense of Lua's `#` operator:
+    /// the number of consecutive non-nil items starting with index 1.
+    /// `None` for non-table values.
+    #[must_use]
+    pub fn len(&self) -> Option<usize> {
+        Some(self.as_table()?.iter_array().count())
+    }
+    /// Check if `len` is zero.
+    /// `None` for non-table values.
+    #[must_use]
+    pub fn is_empty(&self) -> Option<bool> {
+        Some(self.as_table()?.iter_array().next().is_none())
+    }
+    /// Numeric value as a float, converting integers (like Lua does
+    /// in arithmetic). `None` for non-numeric values.
+    #[must_use]
+    pub fn coerce_integer_to_float(&self) -> Option<f64> {
+        match *self {
+            Self::Integer(value) => Some(f64::from(value)),
+            Self::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// Lua's `+`: integers add to an integer (`None` on overflow),
+    /// and to a float if either operand is a float.
+    /// Strings are converted to numbers by Lua's rules:
+    /// decimal or hexadecimal (`0x10`, `0x1p4`) numerals
+    /// with optional surrounding whitespace, but not `inf` or `nan`.
+    /// Unlike Lua, integers are 32-bit: hexadecimal integers wrap around
+    /// modulo `2 ** 32`, and decimal ones that do not fit become floats.
+    /// `None` for other values.
+    #[must_use]
+    pub fn arithmetic_add(&self, other: &Self) -> Option<Self> {
+        match (self.arithmetic_operand()?, other.arithmetic_operand()?) {
+            (Self::Integer(a), Self::Integer(b)) =>
+                Some(Self::Integer(a.checked_add(b)?)),
+            (a, b) => Some(Self::Float(
+                a.coerce_integer_to_float()? + b.coerce_integer_to_float()? )),
+        }
+    }
+    fn arithmetic_operand(&self) -> Option<Self> {
+        match *self {
+            Self::Integer(_) | Self::Float(_) => Some(self.clone()),
+            Self::String(ref value) => str_to_number(value),
+            _ => None,
+        }
+    }
+    /// Total length in bytes of all strings in the value,
+    /// including string keys of nested tables.
+    /// Non-UTF-8 strings are counted as well.
+    #[must_use]
+    pub fn total_string_bytes(&self) -> usize {
+        match *self {
+            Self::Boolean(_) | Self::Integer(_) | Self::Float(_) => 0,
+            Self::String(ref value) => value.len(),
+            Self::Bytes(ref value) => value.len(),
+            Self::Table(ref table) => table.iter()
+                .map(|(key, value)| {
+                    key.as_name().map_or(0, str::len) +
+                        value.total_string_bytes()
+                })
+                .sum(),
+        }
+    }
+    #[must_use]
+    #[inline]
+    pub fn as_table_mut(&mut self) -> Option<&mut Table> {
+        match *self {
+            Self::Table(ref mut table) => Some(table),
+            _ => None,
+        }
+    }
+}
+
 #[allow(clippy::use_self)]
 impl TryFrom<Value> for Key {
     type Error = crate::error::DumpError;
@@ -136,7 +298,8 @@ impl TryFrom<Value> for Key {
         Ok(match value {
             Value::Integer(number) => Key::Index(number),
             Value::String(string) => Key::Name(string),
-            Value::Boolean(_) | Value::Float(_) | Value::Table(_)
+            Value::Boolean(_) | Value::Float(_) |
+            Value::Bytes(_) | Value::Table(_)
                 => return Err(err_key_from_value()),
         })
     }
@@ -159,6 +322,56 @@ impl From<Key> for Value {
     }
 }
 
+// https://www.lua.org/source/5.4/lobject.c.html#luaO_str2num
+fn str_to_number(string: &str) -> Option<Value> {
+    let string = string.trim_matches(
+        |c| matches!(c, ' ' | '\t' | '\n' | '\x0B' | '\x0C' | '\r') );
+    let (negative, unsigned) = match string.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, string.strip_prefix('+').unwrap_or(string)),
+    };
+    if let Some(hex) = unsigned.strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let bits = hex.chars()
+                .filter_map(|digit| digit.to_digit(16))
+                .fold(0_u32, |bits, digit| bits.wrapping_mul(16) | digit);
+            let value = i32::from_ne_bytes(bits.to_ne_bytes());
+            return Some(Value::Integer(
+                if negative { value.wrapping_neg() } else { value } ));
+        }
+        let value = hex_str_to_float(hex)?;
+        return Some(Value::Float(if negative { -value } else { value }));
+    }
+    if let Ok(value) = string.parse() {
+        return Some(Value::Integer(value));
+    }
+    // Rust would accept "inf", "infinity" and "nan"; Lua rejects them
+    if string.contains(['n', 'N']) { return None; }
+    string.parse().ok().map(Value::Float)
+}
+
+fn hex_str_to_float(hex: &str) -> Option<f64> {
+    //! The part of a hexadecimal float after `0x`,
+    //! e.g. `1.8p-2`; the exponent is optional.
+    let (mantissa, exponent) = match hex.split_once(['p', 'P']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (hex, 0),
+    };
+    let (integral, fractional) = mantissa.split_once('.')
+        .unwrap_or((mantissa, ""));
+    if integral.is_empty() && fractional.is_empty() { return None; }
+    let mut value = 0.0_f64;
+    for digit in integral.chars().chain(fractional.chars()) {
+        value = value.mul_add(16.0, f64::from(digit.to_digit(16)?));
+    }
+    let exponent = i32::try_from(fractional.len()).ok()
+        .and_then(|len| len.checked_mul(4))
+        .and_then(|shift| exponent.checked_sub(shift))?;
+    Some(value * 2_f64.powi(exponent))
+}
+
 
 mod load {
 
@@ -236,6 +449,13 @@ impl BuilderTr for Builder {
         Ok(Some(Value::String(Str::from(value))))
     }
 
+    #[inline]
+    fn build_bytes<E: Error>(self, value: &[u8])
+    -> Result<Option<Value>, E>
+    {
+        Ok(Some(Value::Bytes(Vec::from(value))))
+    }
+
     fn build_table<T>(self, items: T) -> Result<Option<Value>, T::Error>
     where
         T : TableLoader<Key=Self::Key, Value=Self::Value>,
@@ -280,6 +500,8 @@ impl Dump for Value {
                 dumper.dump_float(value),
             Self::String(ref value) =>
                 dumper.dump_string(value),
+            Self::Bytes(ref value) =>
+                dumper.dump_bytes(value),
             Self::Table(ref table) =>
                 dumper.dump_table(table.dump_iter()),
         }
@@ -300,7 +522,7 @@ use crate::{
     },
 };
 
-use super::{Key, Value};
+use super::{Key, Value, Table, SERDE_BYTES_TAG};
 
 impl<'de> Deserialize<'de> for Key {
     fn deserialize<D>(de: D) -> Result<Self, D::Error>
@@ -382,6 +604,12 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
         Ok(Some(Value::String(Str::from(v))))
     }
 
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where E: de::Error
+    {
+        Ok(Some(Value::Bytes(Vec::from(v))))
+    }
+
     fn visit_none<E>(self) -> Result<Self::Value, E>
     where E: de::Error
     {
@@ -408,15 +636,47 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
             super::table::de::TableVisitor::new().visit_seq(seq)? )))
     }
 
-    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where A: de::MapAccess<'de>
     {
+        //! A map with the single key `SERDE_BYTES_TAG` and a hexadecimal
+        //! string value is `Value::Bytes`; anything else is a table.
+        let Some(first_key) = map.next_key::<Key>()? else {
+            return Ok(Some(Value::Table(Table::new())));
+        };
+        let first = (first_key, map.next_value::<Value>()?);
+        let second = match map.next_key::<Key>()? {
+            Some(second_key) => Some((second_key, map.next_value::<Value>()?)),
+            None => match first {
+                (ref tag, Value::String(ref hex))
+                    if tag.as_name() == Some(SERDE_BYTES_TAG) =>
+                {
+                    if let Some(bytes) = bytes_from_hex(hex) {
+                        return Ok(Some(Value::Bytes(bytes)));
+                    }
+                    None
+                },
+                _ => None,
+            },
+        };
         Ok(Some(Value::Table(
-            super::table::de::TableVisitor::new().visit_map(map)? )))
+            super::table::de::TableVisitor::visit_map_after(
+                std::iter::once(first).chain(second), map )? )))
     }
 
 }
 
+fn bytes_from_hex(hex: &str) -> Option<Vec<u8>> {
+    let digit = |digit: u8| char::from(digit).to_digit(16)
+        .and_then(|digit| u8::try_from(digit).ok());
+    let pairs = hex.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() { return None; }
+    pairs.map(|pair| match *pair {
+        [high, low] => Some((digit(high)? << 4) | digit(low)?),
+        _ => None,
+    }).collect()
+}
+
 }
 
 
@@ -426,7 +686,7 @@ use ::serde::{Serialize, ser};
 
 use crate::common::serde as common_serde;
 
-use super::{Key, Value};
+use super::{Key, Value, SERDE_BYTES_TAG};
 
 impl Serialize for Key {
     fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
@@ -448,6 +708,11 @@ impl Serialize for Value {
             Self::Integer(value) => value.serialize(ser),
             Self::Float  (value) => value.serialize(ser),
             Self::String (value) => value.serialize(ser),
+            Self::Bytes  (value) => ser.collect_map(std::iter::once((
+                SERDE_BYTES_TAG,
+                value.iter().map(|byte| format!("{byte:02x}"))
+                    .collect::<String>(),
+            ))),
             Self::Table  (table) => table.serialize(ser),
         }
     }
@@ -461,12 +726,180 @@ common_serde::impl_flat_se_option!(Value);
 #[cfg(test)]
 mod test {
 
-use crate::common::{
-    TransparentRef,
-    serde::{OptionSerdeWrap, OptionRefSerdeWrap},
+use crate::{
+    common::{
+        TransparentRef,
+        serde::{OptionSerdeWrap, OptionRefSerdeWrap},
+    },
+    Str,
 };
 
-use super::Value;
+use super::{Key, Value, Table};
+
+#[test]
+fn test_as_table() {
+    let value = Value::Table(Table::from_iter([(Key::Index(1), Value::Integer(2))]));
+    assert!(value.as_table().is_some_and(|table| table.len() == 1));
+    assert!(Value::Integer(1).as_table().is_none());
+}
+
+#[test]
+fn test_len() {
+    let value = Value::Table(Table::from_iter([
+        (Key::Index(1), Value::Integer(1)),
+        (Key::Index(2), Value::Integer(2)),
+        (Key::Index(4), Value::Integer(4)),
+        (Key::from("a"), Value::Integer(0)),
+    ]));
+    assert_eq!(value.len(), Some(2));
+    assert_eq!(Value::Table(Table::new()).len(), Some(0));
+    assert_eq!(Value::Integer(1).len(), None);
+    assert_eq!(value.is_empty(), Some(false));
+}
+
+#[test]
+fn test_key_try_from_str_integer() {
+    assert_eq!(Key::try_from_str_integer("[1]"), Some(Key::Index(1)));
+    assert_eq!(Key::try_from_str_integer("[-42]"), Some(Key::Index(-42)));
+    assert_eq!(Key::try_from_str_integer("name"), None);
+    assert_eq!(Key::try_from_str_integer("1"), None);
+    assert_eq!(Key::try_from_str_integer("[]"), None);
+    assert_eq!(Key::try_from_str_integer("[a]"), None);
+    assert_eq!(Key::try_from_str_integer("[99999999999]"), None);
+}
+
+#[test]
+fn test_coerce_integer_to_float() {
+    assert_eq!(Value::Integer(-3).coerce_integer_to_float(), Some(-3.0));
+    assert_eq!(Value::Float(0.5).coerce_integer_to_float(), Some(0.5));
+    assert_eq!(Value::String(Str::known("1")).coerce_integer_to_float(), None);
+    assert_eq!(Value::Boolean(true).coerce_integer_to_float(), None);
+}
+
+#[test]
+fn test_arithmetic_add() {
+    let int = Value::Integer;
+    assert!(int(2).arithmetic_add(&int(3)) == Some(int(5)));
+    assert!(int(i32::MAX).arithmetic_add(&int(1)).is_none());
+    assert!(int(2).arithmetic_add(&Value::Float(0.5)) == Some(Value::Float(2.5)));
+    assert!( Value::String(Str::known(" 10 ")).arithmetic_add(&int(1))
+        == Some(int(11)) );
+    assert!( Value::String(Str::known("1.5")).arithmetic_add(&int(1))
+        == Some(Value::Float(2.5)) );
+    assert!(Value::String(Str::known("a")).arithmetic_add(&int(1)).is_none());
+    assert!(Value::Boolean(true).arithmetic_add(&int(1)).is_none());
+}
+
+#[test]
+fn test_arithmetic_add_string_numerals() {
+    let add_one = |value: &'static str|
+        Value::String(Str::known(value)).arithmetic_add(&Value::Integer(1));
+    for value in ["inf", "-inf", "infinity", "nan", "NaN", "0x", "1e", "1 2", "0x1g"] {
+        assert!(add_one(value).is_none(), "{value}");
+    }
+    assert!(add_one("0x10") == Some(Value::Integer(17)));
+    assert!(add_one(" -0X10\t") == Some(Value::Integer(-15)));
+    assert!(add_one("0xFFFFFFFF") == Some(Value::Integer(0)));
+    assert!(add_one("0x1p4") == Some(Value::Float(17.0)));
+    assert!(add_one("0x.8") == Some(Value::Float(1.5)));
+    assert!(add_one("1e2") == Some(Value::Float(101.0)));
+    assert!(add_one("+.5") == Some(Value::Float(1.5)));
+    assert!(add_one("99999999999") == Some(Value::Float(1e11)));
+}
+
+#[test]
+fn test_value_predicates() {
+    let values = [
+        Value::Boolean(true), Value::Integer(1), Value::Float(1.0),
+        Value::String(Str::known("a")), Value::Bytes(vec![0xFF]),
+        Value::Table(Table::new()),
+    ];
+    for (index, value) in values.iter().enumerate() {
+        let predicates = [
+            value.is_bool(), value.is_integer(), value.is_float(),
+            value.is_string(), value.is_bytes(), value.is_table(),
+        ];
+        for (predicate_index, predicate) in predicates.into_iter().enumerate() {
+            assert_eq!(predicate, predicate_index == index, "{}", value.type_name());
+        }
+    }
+    assert_eq!(Value::Integer(1).type_name(), "integer");
+}
+
+#[test]
+fn test_total_string_bytes() {
+    assert_eq!(Value::Integer(1).total_string_bytes(), 0);
+    assert_eq!(Value::Bytes(vec![0xFF, 0]).total_string_bytes(), 2);
+    let value = Value::Table(Table::from_iter([
+        (Key::from("ab"), Value::String(Str::known("фыва"))),
+        (Key::Index(1), Value::Table(Table::from_iter([
+            (Key::from("c"), Value::Float(1.0)),
+        ]))),
+    ]));
+    assert_eq!(value.total_string_bytes(), 2 + 8 + 1);
+}
+
+#[test]
+fn test_as_table_mut() {
+    let mut value = Value::Table(Table::from_iter([(Key::Index(1), Value::Integer(2))]));
+    if let Some(table) = value.as_table_mut() {
+        table[1] = Value::Integer(3);
+    }
+    assert!(value.as_table().is_some_and(|table| table[1] == Value::Integer(3)));
+    assert!(Value::Integer(1).as_table_mut().is_none());
+}
+
+#[test]
+fn test_bytes_dump_load() {
+    let value = Value::Bytes(vec![b'a', 0xFF, b'b']);
+    let exchange = crate::dumper::dump_blueprint::<Value, Value>(
+        crate::Exchange::Blueprint(Some(value.clone())) ).unwrap();
+    let loaded = crate::loader::load_blueprint::<Value, Value, crate::error::LoadError>(
+        &exchange )
+        .unwrap().transpose().unwrap().unwrap();
+    assert!(loaded == value);
+}
+
+#[test]
+fn test_bytes_serde() {
+    let value = Value::Table(Table::from_iter([
+        (Key::from("a"), Value::Bytes(vec![b'a', 0xFF])),
+        (Key::from("b"), Value::Table(Table::from_iter([
+            (Key::Index(1), Value::Bytes(vec![])),
+        ]))),
+    ]));
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(json, r#"{"a":{"$bytes":"61ff"},"b":[{"$bytes":""}]}"#);
+    assert!(serde_json::from_str::<Value>(&json).unwrap() == value);
+    let ron = ron::to_string(&value).unwrap();
+    assert!(ron::from_str::<Value>(&ron).unwrap() == value);
+    // anything else with the tag is still a table
+    for json in [r#"{"$bytes":"6"}"#, r#"{"$bytes":1}"#] {
+        assert!(serde_json::from_str::<Value>(json).unwrap().is_table());
+    }
+    let table = serde_json::from_str::<Value>(r#"{"$bytes":"61","a":1}"#).unwrap();
+    assert_eq!(table.as_table().map(Table::len), Some(2));
+    // a table of the same shape as the encoding is not told apart
+    let table = Value::Table(Table::from_iter([
+        (Key::from("$bytes"), Value::String(Str::known("61"))),
+    ]));
+    let json = serde_json::to_string(&table).unwrap();
+    assert!(serde_json::from_str::<Value>(&json).unwrap() == Value::Bytes(vec![b'a']));
+}
+
+#[test]
+fn test_float_dump_load_bits() {
+    use crate::codec::WireFormat as _;
+    for value in [
+        f64::MIN_POSITIVE, f64::MIN_POSITIVE / 2.0, f64::from_bits(1),
+        -0.0, 0.0, 1e308, -1e308, f64::MAX, f64::MIN,
+        f64::INFINITY, f64::NEG_INFINITY, f64::NAN,
+    ] {
+        let loaded = Value::decode(&Value::Float(value).encode().unwrap()).unwrap();
+        let Value::Float(loaded) = loaded else { panic!("should be a float") };
+        assert_eq!(loaded.to_bits(), value.to_bits(), "{value:e}");
+    }
+}
 
 #[test]
 fn test_value_serde() {
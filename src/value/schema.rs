@@ -0,0 +1,110 @@
+//! Rough description of value shapes, inferred from examples.
+
+use std::collections::BTreeMap;
+
+use crate::Str;
+
+use super::{Key, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScalarType {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Schema {
+    /// Accepts any value (or nothing more specific was inferred).
+    Any,
+    Scalar(ScalarType),
+    /// A table with integer keys, all items accepted by the schema.
+    Array(Box<Self>),
+    /// A table with string keys, each field with its own schema.
+    Object(BTreeMap<Str, Self>),
+}
+
+impl Schema {
+    /// The simplest schema accepting values of both schemas.
+    fn merge(self, other: Self) -> Self {
+        if self == other { self } else { Self::Any }
+    }
+}
+
+impl Value {
+    /// Infer the simplest schema that accepts this value.
+    ///
+    /// Tables with only integer keys become arrays (with the item schema
+    /// falling back to `Any` if items differ), tables with only string
+    /// keys become objects, and tables mixing both become `Any`.
+    #[must_use]
+    pub fn schema_infer(&self) -> Schema {
+        let table = match *self {
+            Self::Boolean(_) => return Schema::Scalar(ScalarType::Boolean),
+            Self::Integer(_) => return Schema::Scalar(ScalarType::Integer),
+            Self::Float  (_) => return Schema::Scalar(ScalarType::Float),
+            Self::String (_) => return Schema::Scalar(ScalarType::String),
+            Self::Bytes  (_) => return Schema::Scalar(ScalarType::Bytes),
+            Self::Table(ref table) => table,
+        };
+        if table.iter().all(|(key, _)| matches!(key, Key::Index(_))) {
+            let item = table.iter()
+                .map(|(_, value)| value.schema_infer())
+                .reduce(Schema::merge)
+                .unwrap_or(Schema::Any);
+            return Schema::Array(Box::new(item));
+        }
+        let mut fields = BTreeMap::new();
+        for (key, value) in table {
+            let Key::Name(name) = key else { return Schema::Any };
+            fields.insert(name, value.schema_infer());
+        }
+        Schema::Object(fields)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+use crate::value::{Key, Value, Table};
+
+use super::{ScalarType, Schema};
+
+#[test]
+fn test_schema_infer() {
+    assert_eq!( Value::Integer(1).schema_infer(),
+        Schema::Scalar(ScalarType::Integer) );
+    let array = Value::Table([
+        (Key::Index(1), Value::Integer(1)),
+        (Key::Index(2), Value::Integer(2)),
+    ].into_iter().collect());
+    assert_eq!( array.schema_infer(),
+        Schema::Array(Box::new(Schema::Scalar(ScalarType::Integer))) );
+    let mixed_array = Value::Table([
+        (Key::Index(1), Value::Integer(1)),
+        (Key::Index(2), Value::Boolean(true)),
+    ].into_iter().collect());
+    assert_eq!(mixed_array.schema_infer(), Schema::Array(Box::new(Schema::Any)));
+    let object = Value::Table([
+        (Key::from("a"), Value::Float(1.0)),
+        (Key::from("b"), array),
+    ].into_iter().collect());
+    let Schema::Object(fields) = object.schema_infer()
+        else { panic!("should be an object") };
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields["a"], Schema::Scalar(ScalarType::Float));
+    let mixed = Value::Table([
+        (Key::Index(1), Value::Integer(1)),
+        (Key::from("a"), Value::Integer(1)),
+    ].into_iter().collect());
+    assert_eq!(mixed.schema_infer(), Schema::Any);
+    assert_eq!( Value::Table(Table::new()).schema_infer(),
+        Schema::Array(Box::new(Schema::Any)) );
+}
+
+}
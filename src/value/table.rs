@@ -1,10 +1,21 @@
-use std::{iter::FusedIterator, ops::Range};
+use std::{
+    collections::{BTreeMap, HashMap},
+    iter::FusedIterator, ops::Range,
+};
 
 use thiserror::Error;
 
+use crate::{
+    common::{LogSize, iexp2, u32_to_usize},
+    error::LoadError,
+    table_iter::AssocItem,
+    value::constants::{MAX_ASSOC_LOGLEN, MAX_MIXED_ARRAY_LEN},
+};
+
 use super::Key;
 
 mod assoc;
+pub use assoc::{RehashError, str_table_hash};
 
 #[derive(Clone)]
 pub struct Table<V> {
@@ -15,8 +26,79 @@ pub struct Table<V> {
 
 impl<V: PartialEq> PartialEq for Table<V> {
     fn eq(&self, other: &Self) -> bool {
+        self.equal_content(other)
+    }
+}
+
+impl<V: PartialEq> Table<V> {
+    /// Compare key-value pairs, regardless of how the tables
+    /// were laid out when loaded.
+    /// Items are kept sorted by key, so they are compared element-wise.
+    #[must_use]
+    pub fn equal_content(&self, other: &Self) -> bool {
         self.items == other.items
     }
+    /// Find the first key (in key order) holding a value equal to `value`.
+    /// This is a linear scan over all items.
+    #[must_use]
+    pub fn find_by_value(&self, value: &V) -> Option<&Key> {
+        self.items.iter()
+            .find(|(_, item_value)| item_value == value)
+            .map(|(key, _)| key)
+    }
+}
+
+impl<V: Clone> Table<V> {
+    /// Convert a table with keys `1, 2, …, len()` into a vector,
+    /// `vec[i]` holding the value of key `i + 1`.
+    /// The elements are never `None`; the type matches
+    /// what `ArrayBuilder` accepts, for the reverse conversion.
+    pub fn to_vec(&self) -> Result<Vec<Option<V>>, TableConversionError> {
+        let mut vec = Vec::with_capacity(self.items.len());
+        for ((key, value), expected) in self.items.iter().zip(1_u32 ..) {
+            match *key {
+                Key::Index(index) if index > 0 => {
+                    if index.unsigned_abs() != expected {
+                        return Err(TableConversionError::Gap(expected));
+                    }
+                    vec.push(Some(value.clone()));
+                },
+                _ => return Err(TableConversionError::NonArrayKey(key.clone())),
+            }
+        }
+        Ok(vec)
+    }
+    /// Pairs of `self` whose keys are absent from `other`.
+    #[must_use]
+    pub fn difference<W>(&self, other: &Table<W>) -> Self {
+        self.filter_by_presence(other, false)
+    }
+    /// Pairs of `self` whose keys are also present in `other`.
+    #[must_use]
+    pub fn intersection<W>(&self, other: &Table<W>) -> Self {
+        self.filter_by_presence(other, true)
+    }
+    fn filter_by_presence<W>(&self, other: &Table<W>, present: bool) -> Self {
+        //! Both tables are sorted, so walk them side by side.
+        let mut other_keys = other.items.iter().map(|(key, _)| key).peekable();
+        let mut table = Self {
+            items: self.items.iter()
+                .filter(|(key, _)| {
+                    while other_keys.next_if(|&other_key| other_key < key).is_some() {}
+                    (other_keys.peek() == Some(&key)) == present
+                })
+                .cloned()
+                .collect(),
+            indices: 0..0,
+        };
+        table.indices_fix();
+        table
+    }
+    /// Clone all pairs into a standard map, discarding the order.
+    #[must_use]
+    pub fn to_map(&self) -> HashMap<Key, V> {
+        self.items.iter().cloned().collect()
+    }
 }
 
 impl<V: std::fmt::Debug> std::fmt::Debug for Table<V> {
@@ -46,11 +128,187 @@ impl<V> Table<V> {
             Err(_) => None,
         }
     }
+    /// Same as `get`.
+    #[must_use]
+    #[inline]
+    pub fn find(&self, key: &Key) -> Option<&V> {
+        self.get(key)
+    }
+    #[must_use]
+    pub fn find_mut(&mut self, key: &Key) -> Option<&mut V> {
+        match self.find_item(key) {
+            Ok(index) => Some(&mut self.items[index].1),
+            Err(_) => None,
+        }
+    }
+    /// Exchange the values of two keys, keeping the keys in place.
+    pub fn swap(&mut self, a: &Key, b: &Key) -> Result<(), SwapError> {
+        let (Ok(a), Ok(b)) = (self.find_item(a), self.find_item(b)) else {
+            return Err(SwapError);
+        };
+        let (a, b) = (usize::min(a, b), usize::max(a, b));
+        if a == b { return Ok(()); }
+        let (left, right) = self.items.split_at_mut(b);
+        std::mem::swap(&mut left[a].1, &mut right[0].1);
+        Ok(())
+    }
+    /// Insert `value` right after the last item of the sequence
+    /// starting with index 1 (like Lua's `table.insert(t, v)`).
+    /// Fails without changes if the array part would become too long
+    /// to be dumped: `MAX_MIXED_ARRAY_LEN` if the table also has
+    /// an associative part.
+    pub fn push(&mut self, value: V) -> Result<(), PushError> {
+        let len = self.iter_array().count();
+        let index = i32::try_from(len).ok()
+            .and_then(|len| len.checked_add(1))
+            .ok_or(PushError)?;
+        let Err(position) = self.find_item(&Key::Index(index)) else {
+            unreachable!("the sequence should end before this index");
+        };
+        self.items.insert(position, (Key::Index(index), value));
+        self.indices.end += 1;
+        if !self.array_part_fits() {
+            self.items.remove(position);
+            self.indices.end -= 1;
+            return Err(PushError);
+        }
+        self.debug_assert_sorted_around(position);
+        Ok(())
+    }
+    /// Remove and return the last item of the sequence
+    /// starting with index 1 (like Lua's `table.remove(t)`).
+    pub fn pop(&mut self) -> Option<V> {
+        let len = self.iter_array().count();
+        let position = self.indices.start + len.checked_sub(1)?;
+        let (_, value) = self.items.remove(position);
+        self.indices.end -= 1;
+        self.debug_assert_sorted_around(position);
+        Some(value)
+    }
+    /// Check that the keys next to `position` are in order
+    /// and that it lies on the right side of the positive index range.
+    /// A cheap local part of `assert_valid`, for mutators.
+    fn debug_assert_sorted_around(&self, position: usize) {
+        let start = position.saturating_sub(1);
+        let end = usize::min(position.saturating_add(2), self.items.len());
+        debug_assert!(
+            self.items[start..end].windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Table invariant violated: keys should be sorted and unique" );
+        debug_assert!(
+            self.items.get(self.indices.end)
+                .is_none_or(|(key, _)| matches!(*key, Key::Name(_))),
+            "Table invariant violated: positive index range is incorrect" );
+    }
+    /// Check the table invariants (sorted unique keys, correct
+    /// positive index range, and a hash layout that would load back),
+    /// panicking if they are violated.
+    /// Does nothing unless debug assertions are enabled.
+    ///
+    /// This lays the associative part out, so it takes linear time;
+    /// mutators only check the invariants around the changed item.
+    pub fn assert_valid(&self) {
+        if !cfg!(debug_assertions) { return; }
+        assert!( self.items.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Table invariant violated: keys should be sorted and unique" );
+        assert!(
+            self.items[..self.indices.start].iter()
+                .all(|(key, _)| matches!(*key, Key::Index(..=0))) &&
+            self.items[self.indices.clone()].iter()
+                .all(|(key, _)| matches!(*key, Key::Index(1..))) &&
+            self.items[self.indices.end..].iter()
+                .all(|(key, _)| matches!(*key, Key::Name(_))),
+            "Table invariant violated: positive index range is incorrect" );
+        let (_, assoc_iter) = self.array_assoc_iter();
+        assoc::Table::from_map_iter(assoc_iter)
+            .validate_positions::<LoadError>()
+            .unwrap_or_else(|error| panic!("Table invariant violated: {error}"));
+    }
+    /// Whether the keys are exactly `1, 2, …, len()`,
+    /// i.e. the table converts to a sequence without losing anything.
+    #[must_use]
+    pub fn is_pure_array(&self) -> bool {
+        self.indices == (0 .. self.items.len()) &&
+            usize::try_from(self.last_index_key())
+                .is_ok_and(|last| last == self.items.len())
+    }
+    /// Write `values` at indices `start_index ..`,
+    /// removing the keys where the value is `None`.
+    /// Fails without changes if any index would be non-positive
+    /// or would not fit.
+    pub fn fill_from_slice(&mut self, start_index: u32, values: &[Option<V>])
+    -> Result<(), FillError>
+    where V: Clone
+    {
+        let start = i32::try_from(start_index).ok()
+            .filter(|&start| start > 0)
+            .ok_or(FillError)?;
+        let Some(last_offset) = values.len().checked_sub(1) else {
+            return Ok(());
+        };
+        let last = i32::try_from(last_offset).ok()
+            .and_then(|offset| start.checked_add(offset))
+            .ok_or(FillError)?;
+        self.items.retain(|(key, _)| !matches!( *key,
+            Key::Index(index) if (start ..= last).contains(&index) ));
+        self.items.extend( (start ..= last).zip(values)
+            .filter_map(|(index, value)| Some((Key::Index(index), value.clone()?))) );
+        self.indices = 0..0;
+        self.sort_items();
+        Ok(())
+    }
+    /// Numbers of integer and string keys.
+    #[must_use]
+    pub fn count_by_type(&self) -> TypeCounts {
+        // integer keys are sorted before string keys
+        TypeCounts {
+            integer_keys: self.indices.end,
+            string_keys: self.items.len() - self.indices.end,
+        }
+    }
+    /// Remove all entries, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.indices = 0..0;
+    }
+    /// Build a table from pairs already sorted by key.
+    /// Sorting is skipped if the pairs are indeed strictly increasing;
+    /// otherwise this falls back to sorting (keeping the last value
+    /// of duplicate keys, same as `TableBuilder`).
+    #[must_use]
+    pub fn from_sorted_pairs<I>(pairs: I) -> Self
+    where I: ExactSizeIterator<Item=(Key, V)>
+    {
+        let mut table = Self {
+            items: Vec::with_capacity(pairs.len()),
+            indices: 0..0,
+        };
+        table.items.extend(pairs);
+        if table.items.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            table.indices_fix();
+        } else {
+            table.sort_items();
+        }
+        table
+    }
+    /// Same as `Table::from`.
+    #[must_use]
+    #[inline]
+    pub fn from_btree_map(map: BTreeMap<Key, V>) -> Self {
+        Self::from(map)
+    }
     pub fn into_builder(self) -> TableBuilder<V> {
         TableBuilder { table: self }
     }
 }
 
+impl<V> From<BTreeMap<Key, V>> for Table<V> {
+    /// The map is already sorted, so no sorting is needed;
+    /// the hash layout is computed on dump, as for any table.
+    fn from(map: BTreeMap<Key, V>) -> Self {
+        Self::from_sorted_pairs(map.into_iter())
+    }
+}
+
 impl<K: Into<Key>, V> FromIterator<(K, V)> for Table<V> {
     fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
         TableBuilder::from_iter(iter).build()
@@ -103,7 +361,8 @@ impl<V> Table<V> {
                     self.indices.start,
                     usize::saturating_sub(
                         self.indices.end,
-                        usize::try_from(1 + self.last_index_key() - index).unwrap_or(0)
+                        // `1 + last` would overflow for `i32::MAX`
+                        usize::try_from(self.last_index_key() - index + 1).unwrap_or(0)
                     )
                 ),
             Key::Index(_) => 0,
@@ -122,6 +381,15 @@ impl<V> Table<V> {
         }
         Err(self.items.len())
     }
+    fn find_name_item(&self, name: &str) -> Option<usize> {
+        //! Same as `find_item`, but avoids constructing a key.
+        let names = &self.items[self.indices.end..];
+        let index = names.binary_search_by(|(key, _)| match *key {
+            Key::Index(_) => std::cmp::Ordering::Less,
+            Key::Name(ref key) => str::cmp(key, name),
+        }).ok()?;
+        Some(self.indices.end + index)
+    }
     fn push_item(&mut self, key: Key, value: V) {
         self.items.push((key, value));
     }
@@ -166,6 +434,34 @@ impl<V> Default for Table<V> {
     fn default() -> Self { Self::new() }
 }
 
+impl<V> std::ops::Index<i32> for Table<V> {
+    type Output = V;
+    fn index(&self, index: i32) -> &V {
+        self.get(&Key::Index(index)).expect("key not found")
+    }
+}
+
+impl<V> std::ops::IndexMut<i32> for Table<V> {
+    fn index_mut(&mut self, index: i32) -> &mut V {
+        self.find_mut(&Key::Index(index)).expect("key not found")
+    }
+}
+
+impl<V> std::ops::Index<&str> for Table<V> {
+    type Output = V;
+    fn index(&self, name: &str) -> &V {
+        let index = self.find_name_item(name).expect("key not found");
+        &self.items[index].1
+    }
+}
+
+impl<V> std::ops::IndexMut<&str> for Table<V> {
+    fn index_mut(&mut self, name: &str) -> &mut V {
+        let index = self.find_name_item(name).expect("key not found");
+        &mut self.items[index].1
+    }
+}
+
 pub struct ArrayBuilder<V> {
     table: Table<V>,
     last_index: i32,
@@ -456,6 +752,55 @@ impl<V> Table<V> {
     pub fn iter(&self) -> ClonedKeysSliceIter<'_, V> {
         <&Self as IntoIterator>::into_iter(self)
     }
+    /// Iterate over values with keys `1, 2, …`, stopping at
+    /// the first missing index (like Lua's `ipairs`).
+    pub fn iter_array(&self) -> impl Iterator<Item=(u32, &V)> + '_ {
+        self.items[self.indices.clone()].iter()
+            .zip(1_u32 ..)
+            .map_while(|((key, value), index)| {
+                (key.as_index().map(i32::unsigned_abs) == Some(index))
+                    .then_some((index, value))
+            })
+    }
+    /// Iterate over items with string keys starting with `prefix`.
+    pub fn keys_starting_with<'s>(&'s self, prefix: &'s str)
+    -> impl Iterator<Item=(&'s Key, &'s V)> + 's {
+        let names = &self.items[self.indices.end..];
+        // names are sorted, so the matching ones are contiguous
+        let start = names.partition_point(|(key, _)| match *key {
+            Key::Index(_) => true,
+            Key::Name(ref key) => &**key < prefix,
+        });
+        names[start..].iter()
+            .take_while(move |(key, _)| key.as_name()
+                .is_some_and(|name| name.starts_with(prefix)) )
+            .map(|(key, value)| (key, value))
+    }
+    /// Iterate over the whole array part as it would be dumped,
+    /// yielding `None` for gaps.
+    pub fn array_iter_all(&self)
+    -> impl ExactSizeIterator<Item=Option<&V>> + '_ {
+        let (array_iter, _) = self.array_assoc_iter();
+        array_iter
+    }
+    /// Iterate over all keys as they would be dumped:
+    /// the array part (with `None` for gaps) followed by the hash part.
+    /// Nil values of hash keys are not retained by the table on load,
+    /// so only array gaps yield `None`.
+    pub fn iter_pairs(&self) -> impl Iterator<Item=(Key, Option<&V>)> + '_ {
+        let (array_iter, assoc_iter) = self.array_assoc_iter();
+        (1 ..).map(Key::Index).zip(array_iter)
+            .chain(assoc_iter.map(|(key, value)| (key, Some(value))))
+    }
+    /// Iterate over the items that do not fit into the array part,
+    /// i.e. the items that would go to the assoc (hash) part when dumped.
+    pub fn iter_hash(&self) -> impl Iterator<Item=(&Key, &V)> + '_ {
+        let indices = self.array_part_indices();
+        TupleRefIter::new(ChainIter::new(
+            self.items[..indices.start].iter(),
+            self.items[indices.end..].iter(),
+        ))
+    }
 }
 
 impl<'s, V> IntoIterator for &'s Table<V> {
@@ -549,11 +894,23 @@ impl<V> Table<V> {
         }
         ArrayIter::new(array_keys, iter)
     }
-    /// Split the map into array and assoc parts
-    fn array_assoc_iter(&self) -> (
-        ArrayRefIter<'_, V>,
-        ClonedKeysIter<'_, V, ChainSliceIter<'_, V>>,
-    ) {
+    /// Separate the array part, as it would be dumped, into a vector
+    /// (`vec[i]` holding the value of key `i + 1`, `None` for gaps),
+    /// and the remaining items into a table of its own.
+    pub fn split_at_array_boundary(mut self) -> (Vec<Option<V>>, Self) {
+        let indices = self.array_part_indices();
+        let mut array = Vec::new();
+        for (key, value) in self.items.drain(indices) {
+            let index = key.as_index().unwrap().unsigned_abs();
+            array.resize_with(u32_to_usize(index - 1), || None);
+            array.push(Some(value));
+        }
+        self.indices = 0..0;
+        self.indices_fix();
+        (array, self)
+    }
+    /// The range of items that should constitute the array part
+    fn array_part_indices(&self) -> Range<usize> {
         let mut indices = self.indices.clone();
         loop {
             let Some(end) = indices.end.checked_sub(1) else { break; };
@@ -567,6 +924,24 @@ impl<V> Table<V> {
             }
             let Some(_) = indices.next_back() else { break; };
         }
+        indices
+    }
+    /// Whether the array part is short enough to be dumped
+    /// together with the assoc part.
+    fn array_part_fits(&self) -> bool {
+        let indices = self.array_part_indices();
+        if indices.len() == self.items.len() { return true; }
+        let Some(last) = indices.end.checked_sub(1) else { return true; };
+        if last < indices.start { return true; }
+        let array_len = self.items[last].0.as_index().unwrap();
+        u32::try_from(array_len).is_ok_and(|len| len <= MAX_MIXED_ARRAY_LEN)
+    }
+    /// Split the map into array and assoc parts
+    fn array_assoc_iter(&self) -> (
+        ArrayRefIter<'_, V>,
+        ClonedKeysIter<'_, V, ChainSliceIter<'_, V>>,
+    ) {
+        let indices = self.array_part_indices();
         let array_items = &self.items[indices.clone()];
         let array_keys = 1 .. array_items.last()
             .map_or(1, |(k, _)| 1 + k.as_index().unwrap());
@@ -580,10 +955,187 @@ impl<V> Table<V> {
     }
 }
 
+impl<V> Table<V> {
+    /// Ratio of occupied slots to all slots in the assoc (hash) part,
+    /// as it would be laid out when dumped.
+    /// Zero if there is no assoc part.
+    #[must_use]
+    pub fn assoc_load_factor(&self) -> f64 {
+        let (_, assoc_iter) = self.array_assoc_iter();
+        assoc::Table::from_map_iter(assoc_iter).load_factor()
+    }
+    /// Check that `assoc_load_factor` lies within `min ..= max`.
+    #[must_use]
+    pub fn is_well_loaded(&self, min: f64, max: f64) -> bool {
+        (min ..= max).contains(&self.assoc_load_factor())
+    }
+    /// Load factor that the associative part would have
+    /// if it was resized to `2 ** loglen` slots.
+    /// Fails if the size is unsupported or too small for the items.
+    pub fn assoc_load_factor_at(&self, loglen: Option<LogSize>)
+    -> Result<f64, RehashError> {
+        if loglen.is_some_and(|loglen| loglen > MAX_ASSOC_LOGLEN) {
+            return Err(RehashError);
+        }
+        let live_len = self.iter_hash().count();
+        let len = u32_to_usize(iexp2(loglen));
+        if live_len > len {
+            return Err(RehashError);
+        }
+        if len == 0 { return Ok(0.0); }
+        Ok(live_len as f64 / len as f64)
+    }
+    /// Build a table from `pairs`, rejecting duplicate keys
+    /// and checking that the items that would go to the associative part
+    /// fit into a hash part of `2 ** loglen` slots.
+    ///
+    /// The table does not keep a hash layout, so `loglen` is only checked
+    /// against; dumping lays the associative part out anew.
+    pub fn load_from_pairs_checked<I>(pairs: I, loglen: Option<LogSize>)
+    -> Result<Self, LoadError>
+    where I: IntoIterator<Item=(Key, V)>
+    {
+        if loglen.is_some_and(|loglen| loglen > MAX_ASSOC_LOGLEN) {
+            return Err(LoadError::from("table size is unsupported"));
+        }
+        let mut pairs: Vec<(Key, V)> = pairs.into_iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| Key::cmp(a, b));
+        if pairs.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(LoadError::from("table keys should be unique"));
+        }
+        let table = Self::from_sorted_pairs(pairs.into_iter());
+        if table.iter_hash().count() > u32_to_usize(iexp2(loglen)) {
+            return Err(LoadError::from(
+                "table size should be enough to hold all items" ));
+        }
+        Ok(table)
+    }
+    /// Statistics of the associative part,
+    /// as it would be laid out when dumped.
+    #[must_use]
+    pub fn stats(&self) -> TableStats {
+        let (_, assoc_iter) = self.array_assoc_iter();
+        assoc::Table::from_map_iter(assoc_iter).stats()
+    }
+    /// Length of the longest hash chain in the associative part,
+    /// as it would be laid out when dumped.
+    #[must_use]
+    pub fn max_chain_length(&self) -> usize {
+        let (_, assoc_iter) = self.array_assoc_iter();
+        assoc::Table::from_map_iter(assoc_iter).max_chain_length()
+    }
+    /// Number of keys of the associative part that would not be
+    /// in their main position in a hash part of `2 ** loglen` slots,
+    /// because another key has the same main position.
+    /// `None` if `loglen` exceeds `MAX_ASSOC_LOGLEN`.
+    #[must_use]
+    pub fn collision_count(&self, loglen: LogSize) -> Option<usize> {
+        if loglen > MAX_ASSOC_LOGLEN { return None; }
+        let mut positions: Vec<u32> = self.iter_hash()
+            .map(|(key, _)| key.position(loglen))
+            .collect();
+        let len = positions.len();
+        positions.sort_unstable();
+        positions.dedup();
+        Some(len - positions.len())
+    }
+    /// Slot index of `key` in the associative part, as it would be dumped.
+    /// Returns `None` if the key is absent or falls into the array part.
+    #[must_use]
+    pub fn position_of(&self, key: &Key) -> Option<u32> {
+        let (_, assoc_iter) = self.array_assoc_iter();
+        assoc::Table::from_map_iter(assoc_iter).position_of(key)
+    }
+    /// The associative part laid out in slots, as it would be dumped.
+    #[must_use]
+    pub fn hash_layout(&self) -> HashLayout<'_, V> {
+        let (_, assoc_iter) = self.array_assoc_iter();
+        HashLayout { table: assoc::Table::from_map_iter(assoc_iter) }
+    }
+    /// Text view of the associative part as it would be dumped,
+    /// for debugging collisions, e.g. `[0:L→2] [1:E] [2:L] [3:D]`:
+    /// live, empty or dead slots, with `→n` giving the absolute position
+    /// of the next item in the chain.
+    #[must_use]
+    pub fn display_hash_layout(&self) -> String {
+        self.hash_layout().table.display_hash_layout()
+    }
+}
+
+/// Slots of the associative (hash) part of a table,
+/// as returned by `Table::hash_layout`.
+pub struct HashLayout<'s, V> {
+    table: assoc::Table<&'s V>,
+}
+
+impl<'s, V> HashLayout<'s, V> {
+    /// The raw slots, without copying; empty if there is no assoc part.
+    #[must_use]
+    pub fn as_slice(&self) -> &[Option<AssocItem<Key, &'s V>>] {
+        self.table.as_slice()
+    }
+    /// Sliding windows of `n` consecutive raw slots,
+    /// e.g. for following hash chains;
+    /// yields nothing if there are fewer than `n` slots.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero.
+    pub fn windows(&self, n: usize)
+    -> impl Iterator<Item=&[Option<AssocItem<Key, &'s V>>]> {
+        self.as_slice().windows(n)
+    }
+}
+
+/// Layout statistics of the associative (hash) part of a table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct TableStats {
+    pub total_slots: usize,
+    pub live_slots: usize,
+    pub dead_slots: usize,
+    /// Empty slots
+    pub nil_slots: usize,
+    pub max_chain_length: usize,
+    /// Same as `Table::assoc_load_factor`
+    pub load_factor: f64,
+}
+
+/// Numbers of keys of each type in a table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TypeCounts {
+    pub integer_keys: usize,
+    pub string_keys: usize,
+}
+
+#[derive(Debug, Error)]
+#[error("Array part should not exceed the supported length")]
+#[non_exhaustive]
+pub struct PushError;
+
+#[derive(Debug, Error)]
+#[error("Array indices should be positive and should not overflow")]
+#[non_exhaustive]
+pub struct FillError;
+
+#[derive(Debug, Error)]
+#[error("Both keys should be present in the table")]
+#[non_exhaustive]
+pub struct SwapError;
+
 #[derive(Debug, Error)]
 #[error("The sequence cannot contain None")]
 pub struct NonContinuousError;
 
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TableConversionError {
+    #[error("Table key {0:?} is not an array index")]
+    NonArrayKey(Key),
+    #[error("Array index {0} is missing")]
+    Gap(u32),
+}
+
 // Halts iteration if an error is encountered.
 // (Also acts as a fuse.)
 struct HaltingIter<V, E, I>
@@ -645,7 +1197,7 @@ impl<V> Table<V> {
 pub(super) mod load {
 
 use crate::{
-    common::iexp2,
+    common::iexp2_checked,
     table_iter::TableItem,
     load::{Error, TableLoader},
 };
@@ -662,10 +1214,11 @@ impl<V> Table<V> {
     {
         let array_len = items.array_len();
         let assoc_loglen = items.assoc_loglen();
-        let assoc_len = iexp2(assoc_loglen);
+        let assoc_len = iexp2_checked(assoc_loglen)
+            .ok_or_else(|| T::Error::from("table size is unsupported"))?;
         let mut array = ArrayBuilder::new();
         let mut assoc = AssocTable::new_load_builder(assoc_loglen);
-        assoc.set_last_free(items.assoc_last_free());
+        assoc.set_last_free::<T::Error>(items.assoc_last_free())?;
         let mut array_index = 0;
         let mut assoc_index = 0;
         for item in items {
@@ -685,6 +1238,7 @@ impl<V> Table<V> {
                     let index = assoc_index;
                     assoc_index += 1;
                     assoc.insert(index, assoc_item);
+                    assoc.validate_slot::<T::Error>(index)?;
                 },
                 (None, false, true) => assoc_index += 1,
                 (Some(TableItem::Assoc(_)), true, _) =>
@@ -841,7 +1395,7 @@ use serde::{Deserialize, de};
 
 use crate::common::serde::{DeserializeOption, OptionSerdeWrap};
 
-use super::{Table, ArrayBuilder};
+use super::{Key, Table, ArrayBuilder};
 
 impl<'de, V> Deserialize<'de> for Table<V>
 where V: DeserializeOption<'de>
@@ -884,17 +1438,39 @@ where V: DeserializeOption<'de>
         Ok(table_builder.build())
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
     where A: de::MapAccess<'de>
+    {
+        fill_from_map(Table::new(), map)
+    }
+
+}
+
+impl<V> TableVisitor<V> {
+    /// Same as `visit_map`, with the first entries already taken from `map`.
+    pub(crate) fn visit_map_after<'de, I, A>(taken: I, map: A)
+    -> Result<Table<V>, A::Error>
+    where
+        V: DeserializeOption<'de>, A: de::MapAccess<'de>,
+        I: IntoIterator<Item=(Key, V)>,
     {
         let mut table = Table::new();
-        while let Some((key, value)) = map.next_entry()? {
+        for (key, value) in taken {
             table.push_item(key, value);
         }
-        table.sort_items();
-        Ok(table)
+        fill_from_map(table, map)
     }
+}
 
+fn fill_from_map<'de, V, A>(mut table: Table<V>, mut map: A)
+-> Result<Table<V>, A::Error>
+where V: DeserializeOption<'de>, A: de::MapAccess<'de>
+{
+    while let Some((key, value)) = map.next_entry()? {
+        table.push_item(key, value);
+    }
+    table.sort_items();
+    Ok(table)
 }
 
 }
@@ -929,9 +1505,16 @@ where V: SerializeOption
 #[cfg(test)]
 mod test {
 
-use crate::Str;
+use crate::{
+    Str, table_iter::AssocItem,
+    common::u32_to_usize,
+    value::constants::{MAX_ASSOC_LOGLEN, MAX_MIXED_ARRAY_LEN},
+};
 
-use super::{Key, TableBuilder};
+use super::{
+    ArrayBuilder, Key, Table, TableBuilder,
+    TableConversionError, PushError, RehashError, SwapError, FillError,
+};
 
 use super::dedup_assign;
 
@@ -989,5 +1572,476 @@ fn test_insert_remove() {
         == test_keys );
 }
 
+#[test]
+fn test_find_mut() {
+    let mut table = TableBuilder::new();
+    table.insert(Key::Index(1), 10);
+    table.insert(Key::from("a"), 20);
+    let mut table = table.build();
+    *table.find_mut(&Key::from("a")).unwrap() += 1;
+    assert!(table.find_mut(&Key::Index(2)).is_none());
+    assert_eq!(table.find(&Key::from("a")), Some(&21));
+    assert_eq!(table.find(&Key::Index(1)), Some(&10));
+}
+
+#[test]
+fn test_iter_array() {
+    let table = [1, 2, 4, -1].into_iter()
+        .map(|i| (Key::Index(i), i))
+        .collect::<super::Table<_>>();
+    assert_eq!(
+        table.iter_array().collect::<Vec<_>>(),
+        vec![(1, &1), (2, &2)] );
+}
+
+#[test]
+fn test_equal_content() {
+    let table: Table<i32> = [
+        (Key::from("a"), 1), (Key::Index(1), 2),
+    ].into_iter().collect();
+    let other: Table<i32> = [
+        (Key::Index(1), 2), (Key::from("a"), 1),
+    ].into_iter().collect();
+    assert!(table.equal_content(&other));
+    assert!(!table.equal_content(&Table::new()));
+}
+
+#[test]
+fn test_push() {
+    let mut table: Table<i32> = [
+        (Key::Index(1), 1), (Key::Index(3), 3), (Key::from("a"), 0),
+    ].into_iter().collect();
+    table.push(2).unwrap();
+    table.push(4).unwrap();
+    assert_eq!( table.iter_array().collect::<Vec<_>>(),
+        [(1, &1), (2, &2), (3, &3), (4, &4)] );
+    assert_eq!(table["a"], 0);
+    let mut empty = Table::new();
+    empty.push(1).unwrap();
+    assert_eq!(empty[1], 1);
+}
+
+#[test]
+fn test_push_mixed_array_limit() {
+    let max = i32::try_from(MAX_MIXED_ARRAY_LEN).unwrap();
+    let mut table = Table::from_sorted_pairs(
+        (1 ..= max).map(|index| (Key::Index(index), ()))
+            .chain([(Key::from("a"), ())]).collect::<Vec<_>>().into_iter() );
+    assert!(matches!(table.push(()), Err(PushError)));
+    assert_eq!(table.len(), u32_to_usize(MAX_MIXED_ARRAY_LEN) + 1);
+    // without the assoc part, the array part is not limited
+    table.items.pop();
+    table.push(()).unwrap();
+    assert_eq!(table.iter_array().count(), u32_to_usize(MAX_MIXED_ARRAY_LEN) + 1);
+}
+
+#[test]
+fn test_pop() {
+    let mut table: Table<i32> = [
+        (Key::Index(1), 1), (Key::Index(2), 2), (Key::Index(4), 4),
+        (Key::from("a"), 0),
+    ].into_iter().collect();
+    assert_eq!(table.pop(), Some(2));
+    assert_eq!(table.pop(), Some(1));
+    assert_eq!(table.pop(), None);
+    assert_eq!((table[4], table["a"]), (4, 0));
+}
+
+#[test]
+fn test_find_by_value() {
+    let table: Table<i32> = [
+        (Key::from("a"), 1), (Key::Index(2), 1), (Key::from("b"), 2),
+    ].into_iter().collect();
+    assert_eq!(table.find_by_value(&1), Some(&Key::Index(2)));
+    assert_eq!(table.find_by_value(&2), Some(&Key::from("b")));
+    assert_eq!(table.find_by_value(&3), None);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_assert_valid() {
+    let mut table: Table<i32> = [
+        (Key::Index(-1), 0), (Key::Index(1), 1), (Key::from("a"), 2),
+    ].into_iter().collect();
+    table.assert_valid();
+    table.items.swap(0, 1);
+    let Err(payload) = std::panic::catch_unwind(|| table.assert_valid())
+        else { panic!("should panic") };
+    let message = payload.downcast_ref::<&str>().copied().unwrap_or_default();
+    assert!(message.contains("sorted"), "{message}");
+}
+
+#[test]
+fn test_swap() {
+    let mut table: Table<i32> = [
+        (Key::Index(1), 1), (Key::from("a"), 2),
+    ].into_iter().collect();
+    table.swap(&Key::from("a"), &Key::Index(1)).unwrap();
+    assert_eq!((table[1], table["a"]), (2, 1));
+    table.swap(&Key::Index(1), &Key::Index(1)).unwrap();
+    assert_eq!(table[1], 2);
+    assert!(matches!(table.swap(&Key::Index(1), &Key::Index(2)), Err(SwapError)));
 }
 
+#[test]
+fn test_clear() {
+    let mut table: Table<i32> = [
+        (Key::Index(1), 1), (Key::from("a"), 2),
+    ].into_iter().collect();
+    let capacity = table.items.capacity();
+    table.clear();
+    assert!(table.is_empty());
+    assert_eq!(table.items.capacity(), capacity);
+    assert_eq!(table.iter_array().count(), 0);
+    let mut builder = table.into_builder();
+    builder.insert(Key::Index(1), 3);
+    assert_eq!(builder.build().iter_array().count(), 1);
+}
+
+#[test]
+fn test_array_iter_all() {
+    let table: Table<i32> = [
+        (Key::Index(1), 1), (Key::Index(3), 3), (Key::Index(4), 4),
+        (Key::Index(100), 100),
+    ].into_iter().collect();
+    assert_eq!( table.array_iter_all().collect::<Vec<_>>(),
+        [Some(&1), None, Some(&3), Some(&4)] );
+    assert_eq!(table.iter_array().count(), 1);
+}
+
+#[test]
+fn test_keys_starting_with() {
+    let table: Table<i32> = [
+        (Key::from("r_b"), 2), (Key::from("q"), 0), (Key::from("r_a"), 1),
+        (Key::from("s"), 3), (Key::Index(1), 4),
+    ].into_iter().collect();
+    assert_eq!( table.keys_starting_with("r_").collect::<Vec<_>>(),
+        [(&Key::from("r_a"), &1), (&Key::from("r_b"), &2)] );
+    assert_eq!(table.keys_starting_with("").count(), 4);
+    assert_eq!(table.keys_starting_with("t").count(), 0);
+}
+
+#[test]
+fn test_iter_pairs() {
+    let table: Table<i32> = [
+        (Key::Index(1), 1), (Key::Index(3), 3), (Key::from("a"), 0),
+    ].into_iter().collect();
+    assert_eq!( table.iter_pairs().collect::<Vec<_>>(), [
+        (Key::Index(1), Some(&1)), (Key::Index(2), None),
+        (Key::Index(3), Some(&3)), (Key::from("a"), Some(&0)),
+    ] );
+}
+
+#[test]
+fn test_iter_hash() {
+    let table = [(Key::Index(1), 1), (Key::Index(100), 100),
+            (Key::Index(-1), -1), (Key::from("a"), 0)]
+        .into_iter().collect::<super::Table<_>>();
+    assert_eq!(
+        table.iter_hash().collect::<Vec<_>>(),
+        vec![(&Key::Index(-1), &-1), (&Key::Index(100), &100),
+            (&Key::from("a"), &0)] );
+}
+
+#[test]
+fn test_index() {
+    let mut table = [(Key::Index(1), 1), (Key::Index(-1), -1),
+            (Key::from("a"), 10), (Key::from("b"), 20)]
+        .into_iter().collect::<super::Table<_>>();
+    assert_eq!(table[1], 1);
+    assert_eq!(table[-1], -1);
+    assert_eq!(table["a"], 10);
+    assert_eq!(table["b"], 20);
+    table["b"] += 1;
+    table[-1] -= 1;
+    assert_eq!(table["b"], 21);
+    assert_eq!(table[-1], -2);
+}
+
+#[test]
+fn test_assoc_load_factor() {
+    let empty = TableBuilder::<()>::new().build();
+    assert!(empty.assoc_load_factor().abs() < f64::EPSILON);
+    let mut table = TableBuilder::new();
+    for s in ["a", "b", "c"] {
+        table.insert(Key::Name(Str::from(s)), ());
+    }
+    let table = table.build();
+    assert!((table.assoc_load_factor() - 0.75).abs() < f64::EPSILON);
+    assert!(table.is_well_loaded(0.5, 1.0));
+    assert!(!table.is_well_loaded(0.0, 0.5));
+    assert!((table.assoc_load_factor_at(Some(3)).unwrap() - 0.375).abs()
+        < f64::EPSILON);
+    assert!(matches!(table.assoc_load_factor_at(Some(1)), Err(RehashError)));
+    assert!(matches!(
+        table.assoc_load_factor_at(Some(MAX_ASSOC_LOGLEN + 1)), Err(RehashError) ));
+    assert!(matches!(table.assoc_load_factor_at(Some(32)), Err(RehashError)));
+    assert!(table.assoc_load_factor_at(Some(MAX_ASSOC_LOGLEN)).unwrap() > 0.0);
+}
+
+#[test]
+fn test_hash_layout() {
+    let table: Table<i32> = [(Key::Index(1), 1)].into_iter().collect();
+    assert!(table.hash_layout().as_slice().is_empty());
+    let table: Table<i32> = [
+        (Key::Index(1), 1), (Key::from("a"), 2), (Key::Index(-1), 3),
+    ].into_iter().collect();
+    let layout = table.hash_layout();
+    let slots = layout.as_slice();
+    assert_eq!(slots.len(), 2);
+    assert!(slots.iter().all(Option::is_some));
+    let position = table.position_of(&Key::from("a")).unwrap();
+    assert!( slots[usize::try_from(position).unwrap()].as_ref()
+        .and_then(AssocItem::value) == Some(&&2) );
+}
+
+#[test]
+fn test_hash_layout_windows() {
+    let table: Table<()> = [(Key::Index(1), ())].into_iter().collect();
+    assert_eq!(table.hash_layout().windows(1).count(), 0);
+    let table: Table<()> = ["a", "b", "c"].into_iter()
+        .map(|name| (Key::from(name), ())).collect();
+    let layout = table.hash_layout();
+    assert_eq!(layout.windows(3).count(), 2);
+    assert_eq!(layout.windows(5).count(), 0);
+    assert!(layout.windows(1).all(|window| window.len() == 1));
+    assert_eq!( layout.windows(4).next().unwrap()
+        .iter().filter(|item| item.is_some()).count(), 3 );
+}
+
+#[test]
+fn test_display_hash_layout() {
+    let table: Table<()> = [(Key::Index(1), ())].into_iter().collect();
+    assert_eq!(table.display_hash_layout(), "");
+    // integer keys are hashed modulo 1 in a hash part of two slots
+    let table: Table<()> = [(Key::Index(-3), ()), (Key::Index(-6), ())]
+        .into_iter().collect();
+    let layout = table.display_hash_layout();
+    assert_eq!(layout.matches(":L").count(), 2);
+    assert_eq!(layout.matches('→').count(), 1);
+}
+
+#[test]
+fn test_load_from_pairs_checked() {
+    let pairs = || [(Key::from("a"), 1), (Key::Index(1), 2), (Key::Index(-1), 3)];
+    let table = Table::load_from_pairs_checked(pairs(), Some(2)).unwrap();
+    assert!(table == pairs().into_iter().collect());
+    // key 1 goes to the array part, leaving two items for the hash part
+    let table = Table::load_from_pairs_checked(pairs(), Some(1)).unwrap();
+    assert!(table == pairs().into_iter().collect());
+    for loglen in [Some(0), None] {
+        let Err(error) = Table::load_from_pairs_checked(pairs(), loglen)
+            else { panic!("should be an error") };
+        assert!(error.to_string().contains("enough to hold"), "{error}");
+    }
+    let Err(error) = Table::load_from_pairs_checked(pairs(), Some(MAX_ASSOC_LOGLEN + 1))
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("unsupported"), "{error}");
+    Table::load_from_pairs_checked([(Key::Index(1), 1)], None).unwrap();
+    let Err(error) = Table::load_from_pairs_checked(
+        [(Key::Index(1), 1), (Key::Index(1), 2)], Some(1) )
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("unique"), "{error}");
+}
+
+#[test]
+fn test_collision_count() {
+    let table: Table<()> = [
+        (Key::Index(-1), ()), (Key::Index(-8), ()), (Key::Index(-15), ()),
+        (Key::Index(-2), ()),
+    ].into_iter().collect();
+    assert_eq!(table.collision_count(3), Some(2));
+    assert_eq!(table.collision_count(0), Some(3));
+    assert_eq!(table.collision_count(MAX_ASSOC_LOGLEN), Some(0));
+    assert_eq!(table.collision_count(MAX_ASSOC_LOGLEN + 1), None);
+    assert_eq!(table.collision_count(32), None);
+    assert_eq!(Table::<()>::new().collision_count(3), Some(0));
+    assert_eq!(table.max_chain_length(), 2);
+    let stats = table.stats();
+    assert_eq!(
+        (stats.total_slots, stats.live_slots, stats.dead_slots,
+            stats.nil_slots, stats.max_chain_length),
+        (4, 4, 0, 0, 2) );
+    assert!((stats.load_factor - table.assoc_load_factor()).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_position_of() {
+    let table: Table<()> = [
+        (Key::Index(1), ()),
+        (Key::Name(Str::from("a")), ()),
+        (Key::Name(Str::from("b")), ()),
+    ].into_iter().collect();
+    assert_eq!(table.position_of(&Key::Index(1)), None);
+    assert_eq!(table.position_of(&Key::Name(Str::from("c"))), None);
+    let a = table.position_of(&Key::Name(Str::from("a"))).unwrap();
+    let b = table.position_of(&Key::Name(Str::from("b"))).unwrap();
+    assert!(a < 2 && b < 2 && a != b);
+}
+
+#[test]
+fn test_from_sorted_pairs() {
+    let pairs = [
+        (Key::Index(-1), 1),
+        (Key::Index(1), 2),
+        (Key::Index(2), 3),
+        (Key::Name(Str::from("a")), 4),
+    ];
+    let table = Table::from_sorted_pairs(pairs.clone().into_iter());
+    table.assert_valid();
+    assert!(table == pairs.clone().into_iter().collect());
+    assert_eq!(table.iter_array().count(), 2);
+    let mut unsorted = pairs.to_vec();
+    unsorted.reverse();
+    unsorted.push((Key::Index(1), 5));
+    let resorted = Table::from_sorted_pairs(unsorted.into_iter());
+    resorted.assert_valid();
+    assert_eq!(resorted.len(), 4);
+    assert_eq!(resorted.get(&Key::Index(1)), Some(&5));
+}
+
+#[test]
+fn test_is_pure_array() {
+    assert!(Table::<()>::new().is_pure_array());
+    let mut table: Table<()> = [(Key::Index(1), ()), (Key::Index(2), ())]
+        .into_iter().collect();
+    assert!(table.is_pure_array());
+    table.push(()).unwrap();
+    assert!(table.is_pure_array());
+    let gap: Table<()> = [(Key::Index(1), ()), (Key::Index(3), ())]
+        .into_iter().collect();
+    assert!(!gap.is_pure_array());
+    let zero: Table<()> = [(Key::Index(0), ()), (Key::Index(1), ())]
+        .into_iter().collect();
+    assert!(!zero.is_pure_array());
+    let name: Table<()> = [(Key::Index(1), ()), (Key::from("a"), ())]
+        .into_iter().collect();
+    assert!(!name.is_pure_array());
+}
+
+#[test]
+fn test_to_vec() {
+    let table: Table<i32> = [(Key::Index(2), 20), (Key::Index(1), 10)]
+        .into_iter().collect();
+    assert_eq!(table.to_vec().unwrap(), vec![Some(10), Some(20)]);
+    assert!(table.to_vec().unwrap().into_iter().collect::<ArrayBuilder<_>>()
+        .build() == table);
+    let gap: Table<i32> = [(Key::Index(1), 10), (Key::Index(3), 30)]
+        .into_iter().collect();
+    let Err(TableConversionError::Gap(2)) = gap.to_vec()
+        else { panic!("should be a gap error") };
+    let name: Table<i32> = [(Key::Index(1), 10), (Key::from("a"), 0)]
+        .into_iter().collect();
+    let Err(TableConversionError::NonArrayKey(Key::Name(_))) = name.to_vec()
+        else { panic!("should be a key error") };
+}
+
+#[test]
+fn test_to_map() {
+    let table: Table<i32> = [
+        (Key::Index(-1), 1), (Key::Index(3), 3), (Key::from("a"), 4),
+    ].into_iter().collect();
+    let map = table.to_map();
+    assert_eq!(map.len(), table.len());
+    for (key, value) in &table {
+        assert_eq!(map.get(&key), Some(value));
+    }
+}
+
+#[test]
+fn test_split_at_array_boundary() {
+    let table: Table<i32> = [
+        (Key::Index(-1), -1), (Key::Index(1), 1), (Key::Index(3), 3),
+        (Key::Index(100), 100), (Key::from("a"), 0),
+    ].into_iter().collect();
+    let expected_len = table.array_iter_all().len();
+    let (array, rest) = table.split_at_array_boundary();
+    assert_eq!(array, vec![Some(1), None, Some(3)]);
+    assert_eq!(array.len(), expected_len);
+    rest.assert_valid();
+    assert_eq!(rest.len(), 3);
+    assert_eq!(rest.get(&Key::Index(100)), Some(&100));
+    assert_eq!(rest.get(&Key::Index(-1)), Some(&-1));
+    assert_eq!(rest.get(&Key::Index(1)), None);
+}
+
+#[test]
+fn test_fill_from_slice() {
+    let mut table: Table<i32> = [
+        (Key::Index(1), 1), (Key::Index(3), 3), (Key::from("a"), 0),
+    ].into_iter().collect();
+    table.fill_from_slice(2, &[Some(20), None, Some(40)]).unwrap();
+    assert_eq!(table.to_map().len(), 4);
+    assert_eq!(table.get(&Key::Index(2)), Some(&20));
+    assert_eq!(table.get(&Key::Index(3)), None);
+    assert_eq!(table.get(&Key::Index(4)), Some(&40));
+    assert!(matches!(table.fill_from_slice(0, &[Some(0)]), Err(FillError)));
+    assert!(matches!(table.fill_from_slice(u32::MAX, &[Some(0)]), Err(FillError)));
+    assert!(matches!(
+        table.fill_from_slice(i32::MAX.unsigned_abs(), &[Some(0), Some(0)]),
+        Err(FillError) ));
+    assert_eq!(table.len(), 4);
+    table.fill_from_slice(i32::MAX.unsigned_abs(), &[Some(5)]).unwrap();
+    assert_eq!(table.get(&Key::Index(i32::MAX)), Some(&5));
+}
+
+#[test]
+fn test_find_max_index() {
+    let table: Table<i32> = [(Key::Index(i32::MAX), 1), (Key::Index(1), 2)]
+        .into_iter().collect();
+    assert_eq!(table.get(&Key::Index(i32::MAX)), Some(&1));
+    assert_eq!(table.get(&Key::Index(1)), Some(&2));
+    assert_eq!(table.get(&Key::Index(2)), None);
+}
+
+#[test]
+fn test_count_by_type() {
+    let table: Table<()> = [
+        (Key::Index(-1), ()), (Key::Index(0), ()), (Key::Index(5), ()),
+        (Key::from("a"), ()), (Key::from("b"), ()),
+    ].into_iter().collect();
+    let counts = table.count_by_type();
+    assert_eq!((counts.integer_keys, counts.string_keys), (3, 2));
+    let empty = Table::<()>::new().count_by_type();
+    assert_eq!((empty.integer_keys, empty.string_keys), (0, 0));
+}
+
+#[test]
+fn test_from_btree_map() {
+    let map: std::collections::BTreeMap<Key, i32> = [
+        (Key::from("a"), 0), (Key::Index(2), 2), (Key::Index(1), 1),
+        (Key::Index(-1), -1),
+    ].into_iter().collect();
+    let table = Table::from_btree_map(map.clone());
+    table.assert_valid();
+    assert!(table == map.clone().into_iter().collect());
+    assert_eq!(table.iter_array().count(), 2);
+    assert_eq!(table.stats().live_slots, 2);
+    assert!(Table::from(map) == table);
+}
+
+#[test]
+fn test_difference_intersection() {
+    let table: Table<i32> = [
+        (Key::Index(-1), -1), (Key::Index(1), 1), (Key::Index(2), 2),
+        (Key::from("a"), 10), (Key::from("b"), 20),
+    ].into_iter().collect();
+    let other: Table<()> = [
+        (Key::Index(2), ()), (Key::Index(3), ()), (Key::from("b"), ()),
+    ].into_iter().collect();
+    let difference = table.difference(&other);
+    difference.assert_valid();
+    assert!(difference == [
+        (Key::Index(-1), -1), (Key::Index(1), 1), (Key::from("a"), 10),
+    ].into_iter().collect());
+    let intersection = table.intersection(&other);
+    intersection.assert_valid();
+    assert!(intersection == [
+        (Key::Index(2), 2), (Key::from("b"), 20),
+    ].into_iter().collect());
+    assert!(table.difference(&Table::<()>::new()) == table);
+    assert!(table.intersection(&Table::<()>::new()).is_empty());
+    assert!(table.difference(&table).is_empty());
+}
+
+}
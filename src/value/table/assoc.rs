@@ -1,6 +1,9 @@
-use crate::
-    common::{LogSize, iexp2, ilog2_ceil, ilog2_exact}
-;
+use thiserror::Error;
+
+use crate::{
+    common::{LogSize, iexp2, ilog2_ceil, ilog2_exact, u32_to_usize},
+    value::constants::{MAX_ASSOC_LOGLEN, STRING_HASH_SEED},
+};
 
 use super::Key;
 
@@ -31,8 +34,10 @@ const fn str_table_hash_with_seed<const SEED: u32>(value: &str) -> u32 {
     hash
 }
 
-pub(crate) const fn str_table_hash(value: &str) -> u32 {
-    str_table_hash_with_seed::<0x_645D_BFCD>(value)
+/// The hash of a string key, before masking to the table size.
+#[must_use]
+pub const fn str_table_hash(value: &str) -> u32 {
+    str_table_hash_with_seed::<STRING_HASH_SEED>(value)
 }
 
 // https://www.lua.org/source/5.4/ltable.c.html#hashint
@@ -47,12 +52,20 @@ pub(crate) const fn int_table_hash(value: i32, loglen: LogSize) -> u32 {
 
 impl Key {
     #[inline]
-    fn position(&self, loglen: LogSize) -> u32 {
+    pub(super) fn position(&self, loglen: LogSize) -> u32 {
         match *self {
             Self::Index(index) => int_table_hash(index, loglen),
             Self::Name(ref value) => str_table_hash(value) & mask(loglen),
         }
     }
+    /// Check if both keys have the same main position
+    /// in a hash part of `2 ** loglen` slots.
+    /// `None` if `loglen` exceeds `MAX_ASSOC_LOGLEN`.
+    #[must_use]
+    pub fn collides_with(&self, other: &Self, loglen: LogSize) -> Option<bool> {
+        if loglen > MAX_ASSOC_LOGLEN { return None; }
+        Some(self.position(loglen) == other.position(loglen))
+    }
 }
 
 
@@ -61,14 +74,20 @@ pub(crate) type Item<V> = crate::table_iter::AssocItem<Key, V>;
 impl<V> Item<V> {
     #[inline]
     fn main_position(&self, loglen: LogSize) -> Option<u32> {
-        match self {
-            Self::Dead { .. } => None,
-            Self::Live { key, .. } => Some(key.position(loglen))
-        }
+        self.key().map(|key| key.position(loglen))
     }
 }
 
 
+#[derive(Debug, Error)]
+#[error("last free index should not exceed table size")]
+pub(super) struct RangeError;
+
+#[derive(Debug, Error)]
+#[error("table size should be supported and enough to hold all live items")]
+#[non_exhaustive]
+pub struct RehashError;
+
 #[derive(Debug, Clone)]
 pub(super) struct Table<V> {
     // Invariant:
@@ -109,6 +128,109 @@ impl<V> Table<V> {
     pub(super) fn last_free(&self) -> u32 {
         self.last_free
     }
+    pub(super) fn set_last_free(&mut self, last_free: u32)
+    -> Result<(), RangeError> {
+        if u32::try_from(self.len()).is_ok_and(|len| last_free > len) {
+            return Err(RangeError);
+        }
+        self.last_free = last_free;
+        Ok(())
+    }
+    /// The raw slots; empty if the table has no slots.
+    pub(super) fn as_slice(&self) -> &[Option<Item<V>>] {
+        self.items.as_deref().unwrap_or(&[])
+    }
+    pub(super) fn live_len(&self) -> usize {
+        self.as_slice().iter()
+            .filter(|item| item.as_ref().is_some_and(Item::is_live))
+            .count()
+    }
+    pub(super) fn load_factor(&self) -> f64 {
+        //! Returns zero for a table without slots.
+        let len = self.len();
+        if len == 0 { return 0.0; }
+        self.live_len() as f64 / len as f64
+    }
+    pub(super) fn stats(&self) -> super::TableStats {
+        let mut stats = super::TableStats {
+            total_slots: self.len(),
+            live_slots: 0, dead_slots: 0, nil_slots: 0,
+            max_chain_length: self.max_chain_length(),
+            load_factor: 0.0,
+        };
+        for item in self.as_slice() {
+            match item {
+                Some(Item::Live { .. }) => stats.live_slots += 1,
+                Some(Item::Dead { .. }) => stats.dead_slots += 1,
+                None => stats.nil_slots += 1,
+            }
+        }
+        if stats.total_slots > 0 {
+            stats.load_factor =
+                stats.live_slots as f64 / stats.total_slots as f64;
+        }
+        stats
+    }
+    pub(super) fn max_chain_length(&self) -> usize {
+        //! Chains start at items that are in their main position.
+        let Some(items) = self.items.as_deref() else { return 0 };
+        let Some(loglen) = self.loglen() else { return 0 };
+        let mut max_length = 0;
+        for (start, item) in items.iter().enumerate() {
+            let Some(item) = item else { continue };
+            let Ok(start) = u32::try_from(start) else { break };
+            if item.main_position(loglen) != Some(start) { continue; }
+            let mut position = start;
+            let mut length = 1;
+            while length < items.len() {
+                let link = items[u32_to_usize(position)].as_ref()
+                    .map_or(0, Item::link);
+                let Some(next) = position.checked_add_signed(link)
+                    .filter(|_| link != 0)
+                    else { break };
+                position = next;
+                length += 1;
+            }
+            max_length = usize::max(max_length, length);
+        }
+        max_length
+    }
+    pub(super) fn position_of(&self, key: &Key) -> Option<u32> {
+        let items = self.items.as_deref()?;
+        let loglen = self.loglen()?;
+        let mut position = key.position(loglen);
+        for _ in 0 .. items.len() {
+            let item = items[u32_to_usize(position)].as_ref()?;
+            if item.key() == Some(key) {
+                return Some(position);
+            }
+            let link = item.link();
+            if link == 0 { return None; }
+            position = position.checked_add_signed(link)?;
+        }
+        None
+    }
+    /// Text view of the slots for debugging collisions, e.g.
+    /// `[0:L→2] [1:E] [2:L] [3:D]`: live, empty or dead slots,
+    /// with `→n` giving the absolute position of the next chain item.
+    pub(super) fn display_hash_layout(&self) -> String {
+        let Some(items) = self.items.as_deref() else { return String::new() };
+        items.iter().enumerate().map(|(position, item)| {
+            let state = match item {
+                Some(Item::Live { .. }) => "L",
+                Some(Item::Dead { .. }) => "D",
+                None => "E",
+            };
+            let link = item.as_ref().map_or(0, Item::link);
+            let target = i64::try_from(position).ok()
+                .and_then(|position| position.checked_add(link.into()));
+            match target {
+                Some(target) if link != 0 =>
+                    format!("[{position}:{state}→{target}]"),
+                _ => format!("[{position}:{state}]"),
+            }
+        }).collect::<Vec<_>>().join(" ")
+    }
 }
 
 impl<V> Table<V> {
@@ -255,13 +377,10 @@ impl<V> TableBuilder<V> {
         }
         let mut prev_index = other_index;
         loop {
-            let link = match self.get_mut(prev_index)
-                .as_ref().unwrap()
-            {
-                &Item::Dead { link } | &Item::Live { link, .. }
-                    if link != 0 => link,
-                _ => unreachable!("table structure is broken"),
-            };
+            let link = self.get(prev_index).as_ref().unwrap().link();
+            if link == 0 {
+                unreachable!("table structure is broken");
+            }
             let Some(next_index) = prev_index.checked_add_signed(link) else {
                 unreachable!("table structure is broken")
             };
@@ -326,10 +445,6 @@ impl<V> TableLoadBuilder<V> {
             return Err(E::from(
                 "the table should not be that large" ));
         }
-        if self.table.last_free > self.table.len() as u32 {
-            return Err(E::from(
-                "last free index should not exceed table size" ));
-        }
         self.table.validate_positions::<E>()?;
         Ok(self.table)
     }
@@ -341,14 +456,37 @@ impl<V> TableLoadBuilder<V> {
         assert!(old_item.is_none());
     }
 
-    pub(crate) fn set_last_free(&mut self, last_free: u32) {
-        self.table.last_free = last_free;
+    pub(crate) fn validate_slot<E: Error>(&self, index: u32)
+    -> Result<(), E> {
+        //! Check the invariants of a single inserted slot.
+        //! Whole-chain invariants are only checked by `build`.
+        let Some(items) = self.table.items.as_deref() else {
+            return Err(E::from("table should have slots"));
+        };
+        let len = items.len();
+        let Some(item) = items.get(u32_to_usize(index)) else {
+            return Err(E::from("slot index should be within bounds"));
+        };
+        let Some(link) = item.as_ref().map(Item::link)
+            else { return Ok(()) };
+        if index.checked_add_signed(link)
+            .is_some_and(|next| u32_to_usize(next) < len)
+        {
+            return Ok(());
+        }
+        Err(E::from("assoc node link should lead within bounds"))
+    }
+
+    pub(crate) fn set_last_free<E: Error>(&mut self, last_free: u32)
+    -> Result<(), E> {
+        self.table.set_last_free(last_free)
+            .map_err(|error| E::from(error.to_string().as_str()))
     }
 
 }
 
 impl<V> Table<V> {
-    pub(super) fn validate_positions<E: Error>(&self) -> Result<(), E> {
+    pub(in super::super) fn validate_positions<E: Error>(&self) -> Result<(), E> {
         let Some(items) = self.items.as_deref() else {
             return Ok(());
         };
@@ -375,10 +513,8 @@ impl<V> Table<V> {
                 if unvalidated[index] == Some(main_position) {
                     unvalidated[index] = None;
                 }
-                let link = match items[index] {
-                    Some(Item::Dead { link } | Item::Live { link, .. })
-                        if link != 0
-                        => link,
+                let link = match items[index].as_ref().map(Item::link) {
+                    Some(link) if link != 0 => link,
                     _ => break,
                 };
                 let Some(next_position) = position.checked_add_signed(link)
@@ -451,7 +587,148 @@ impl<'s, V> ExactSizeIterator for TableDumpIter<'s, V> {
 #[cfg(test)]
 mod test {
 
-use super::{Key, TableBuilder};
+use crate::{
+    common::u32_to_usize,
+    value::constants::MAX_ASSOC_LOGLEN,
+};
+
+use super::{Key, TableBuilder, RangeError, int_table_hash, str_table_hash, mask};
+
+#[test]
+fn test_hash_distribution() {
+    const LOGLEN: u8 = 10;
+    const COUNT: usize = 1000;
+    // a fixed linear congruential generator keeps the test deterministic
+    let mut state: u32 = 0x_1234_5678;
+    let mut next = || {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        state >> 16
+    };
+    let mut buckets = [0_usize; 1 << LOGLEN];
+    for _ in 0 .. COUNT {
+        let len = 1 + next() % 16;
+        let name: String = (0 .. len)
+            .map(|_| char::from(b'!' + u8::try_from(next() % 94).unwrap()))
+            .collect();
+        buckets[u32_to_usize(str_table_hash(&name) & mask(LOGLEN))] += 1;
+    }
+    let max_chain = buckets.iter().copied().max().unwrap();
+    assert!(max_chain <= 7, "max chain length {max_chain} is too large");
+}
+
+#[test]
+fn test_int_table_hash() {
+    // `hashint` from Lua 5.4, with `lua_Unsigned` being 32-bit:
+    // the key is reinterpreted as unsigned and taken modulo
+    // `(sizenode - 1) | 1`
+    fn reference(value: i32, loglen: u8) -> u32 {
+        let modulus = ((1_u64 << loglen) - 1) | 1;
+        u32::try_from(u64::from(value as u32) % modulus).unwrap()
+    }
+    for loglen in 0 ..= 31 {
+        for value in [i32::MIN, i32::MIN + 1, -2, -1, 0, 1, 2, i32::MAX - 1, i32::MAX] {
+            assert_eq!( int_table_hash(value, loglen), reference(value, loglen),
+                "value {value}, loglen {loglen}" );
+            assert!(loglen == 0 || int_table_hash(value, loglen) < (1 << loglen));
+        }
+    }
+    assert_eq!(int_table_hash(-1, 3), 3);
+    assert_eq!(int_table_hash(7, 3), 0);
+    assert_eq!(int_table_hash(8, 3), 1);
+    assert_eq!(int_table_hash(i32::MAX, 31), 0);
+    assert_eq!(int_table_hash(i32::MIN, 31), 1);
+}
+
+#[test]
+fn test_collides_with() {
+    // integer keys are hashed modulo `2 ** loglen - 1`
+    assert_eq!(Key::Index(1).collides_with(&Key::Index(8), 3), Some(true));
+    assert_eq!(Key::Index(-1).collides_with(&Key::Index(-2), 3), Some(false));
+    assert_eq!(Key::from("a").collides_with(&Key::from("b"), 0), Some(true));
+    assert_eq!(Key::Index(1).collides_with(&Key::Index(8), 32), None);
+    assert_eq!(
+        Key::Index(1).collides_with(&Key::Index(8), MAX_ASSOC_LOGLEN + 1),
+        None );
+}
+
+#[test]
+fn test_max_chain_length() {
+    assert_eq!(TableBuilder::<()>::new(None).build().max_chain_length(), 0);
+    let mut table_builder = TableBuilder::new(Some(3));
+    for index in [-1, -8, -15, -2] {
+        table_builder.insert(Key::Index(index), ());
+    }
+    assert_eq!(table_builder.build().max_chain_length(), 3);
+}
+
+#[test]
+fn test_display_hash_layout() {
+    assert_eq!(TableBuilder::<()>::new(None).build().display_hash_layout(), "");
+    let mut table_builder = TableBuilder::new(Some(2));
+    // both keys hash to position 0, modulo 3
+    table_builder.insert(Key::Index(3), ());
+    table_builder.insert(Key::Index(6), ());
+    let layout = table_builder.build().display_hash_layout();
+    assert_eq!(layout.matches(":L").count(), 2);
+    assert_eq!(layout.matches(":E").count(), 2);
+    assert_eq!(layout.matches('→').count(), 1);
+    assert!(layout.starts_with("[0:L→"));
+}
+
+#[test]
+fn test_as_slice() {
+    assert!(TableBuilder::<()>::new(None).build().as_slice().is_empty());
+    let mut table_builder = TableBuilder::new(Some(2));
+    table_builder.insert(Key::Index(3), ());
+    let table = table_builder.build();
+    let slots = table.as_slice();
+    assert_eq!(slots.len(), 4);
+    assert!(slots[0].as_ref().is_some_and(|item| item.key() == Some(&Key::Index(3))));
+    assert!(slots[1..].iter().all(Option::is_none));
+}
+
+#[test]
+fn test_set_last_free() {
+    let mut table = TableBuilder::<()>::new(Some(2)).build();
+    assert_eq!(table.last_free(), 4);
+    table.set_last_free(1).unwrap();
+    assert_eq!(table.last_free(), 1);
+    assert!(matches!(table.set_last_free(5), Err(RangeError)));
+    assert_eq!(table.last_free(), 1);
+}
+
+#[test]
+fn test_position_of() {
+    let mut table_builder = TableBuilder::new(Some(1));
+    table_builder.insert(Key::Index(-1), ());
+    table_builder.insert(Key::Index(-3), ());
+    let table = table_builder.build();
+    let mut positions = [
+        table.position_of(&Key::Index(-1)),
+        table.position_of(&Key::Index(-3)),
+    ];
+    positions.sort_unstable();
+    assert_eq!(positions, [Some(0), Some(1)]);
+    assert_eq!(table.position_of(&Key::Index(-5)), None);
+    let empty = TableBuilder::<()>::new(None).build();
+    assert_eq!(empty.position_of(&Key::Index(-1)), None);
+}
+
+#[test]
+fn test_validate_slot() {
+    use crate::{error::LoadError, table_iter::AssocItem};
+    let mut builder = super::Table::<()>::new_load_builder(Some(1));
+    builder.insert(0, AssocItem::Live {
+        key: Key::Index(-2), value: Some(()), link: 1 });
+    builder.validate_slot::<LoadError>(0).unwrap();
+    builder.insert(1, AssocItem::Dead { link: 1 });
+    let Err(error) = builder.validate_slot::<LoadError>(1)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("link"), "{error}");
+    let Err(error) = builder.validate_slot::<LoadError>(2)
+        else { panic!("should be an error") };
+    assert!(error.to_string().contains("slot index"), "{error}");
+}
 
 #[test]
 fn test_dead_insert() {
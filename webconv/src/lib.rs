@@ -127,16 +127,27 @@ pub fn decode(encoded: &str, params: &DecodeParameters)
             serialize::<Exchange<Blueprint, Behavior>>(
                 load_blueprint(encoded)?,
                 params ),
-        InterRepr::MapTree => {
-            let value = load::<_,_,LoadError>(encoded)?
-                .transpose().ok_or_else(|| JsError::new(
-                    "Blueprint or behavior should not \
-                    be represented with nil" ))?;
-            serialize::<Exchange<Value>>(value, params)
-        }
+        InterRepr::MapTree =>
+            serialize::<Exchange<Value>>(load_value(encoded)?, params),
     }
 }
 
+/// Decode into a plain JavaScript object,
+/// structured the same as the `map_tree` JSON output of `decode`.
+#[wasm_bindgen]
+pub fn load_blueprint_wasm(encoded: &str) -> Result<JsValue, JsError> {
+    let json = json::ser::to_string(&load_value(encoded)?)?;
+    js_sys::JSON::parse(&json)
+        .map_err(|_error| JsError::new("JSON should be parsed by JavaScript"))
+}
+
+fn load_value(encoded: &str) -> Result<Exchange<Value>, JsError> {
+    load::<_,_,LoadError>(encoded)?
+        .transpose().ok_or_else(|| JsError::new(
+            "Blueprint or behavior should not \
+            be represented with nil" ))
+}
+
 fn serialize<V>(value: V, params: &DecodeParameters)
 -> Result<String, JsError>
 where V: Serialize